@@ -0,0 +1,276 @@
+//! The cost module prices schedules from employee and role wages, overtime rules and a weekly labor budget.
+use super::emp::{Employee, Roster};
+use super::time::{Day, Schedule, Shift};
+#[cfg(feature = "project")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+// ==============================================
+
+/// The store's pay rules: hourly rates by role, overtime, and the weekly labor budget.
+///
+/// An employee's own rate (see `Employee::set_rate`) takes precedence over any role rate.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub struct Wages {
+    default_rate: f64,
+    #[cfg_attr(feature = "project", serde(serialize_with = "crate::project::sorted"))]
+    role_rates: HashMap<String, f64>,
+    ot_after: usize,
+    ot_multiplier: f64,
+    budget: Option<f64>,
+}
+
+impl Default for Wages {
+    fn default() -> Self {
+        Wages::new()
+    }
+}
+
+impl Wages {
+    // Constructor
+    pub fn new() -> Wages {
+        //! Create pay rules with no rates, overtime at time-and-a-half past 40 hours, and no budget.
+        Wages {
+            default_rate: 0.0,
+            role_rates: HashMap::new(),
+            ot_after: 40,
+            ot_multiplier: 1.5,
+            budget: None,
+        }
+    }
+    // Modification
+    pub fn set_default_rate(&mut self, rate: f64) -> &mut Wages {
+        //! Set the hourly rate for anyone without their own or a role rate.
+        self.default_rate = rate;
+        self
+    }
+    pub fn set_role_rate(&mut self, role: &str, rate: f64) -> &mut Wages {
+        //! Set the hourly rate for employees with a role.
+        self.role_rates.insert(role.to_owned(), rate);
+        self
+    }
+    pub fn set_overtime(&mut self, after_hours: usize, multiplier: f64) -> &mut Wages {
+        //! Pay `multiplier` times the usual rate for every hour worked past `after_hours` in the week.
+        self.ot_after = after_hours;
+        self.ot_multiplier = multiplier;
+        self
+    }
+    pub fn set_budget(&mut self, budget: Option<f64>) -> &mut Wages {
+        //! Set (or clear) the most the week's labor may cost.
+        self.budget = budget;
+        self
+    }
+    // Access
+    pub fn budget(&self) -> Option<f64> {
+        self.budget
+    }
+    pub fn overtime_after(&self) -> usize {
+        self.ot_after
+    }
+    pub fn overtime_multiplier(&self) -> f64 {
+        self.ot_multiplier
+    }
+    pub fn rate_for(&self, em: &Employee) -> f64 {
+        //! Return an employee's hourly rate: their own, else the highest of their roles', else the default.
+        if let Some(rate) = em.rate() {
+            return rate;
+        }
+        em.roles()
+            .iter()
+            .filter_map(|role| self.role_rates.get(role))
+            .cloned()
+            .fold(None, |best: Option<f64>, r| {
+                Some(best.map_or(r, |b| b.max(r)))
+            })
+            .unwrap_or(self.default_rate)
+    }
+    pub fn rate_of(&self, id: &str, ros: &Roster) -> f64 {
+        //! Return the hourly rate for an employee by ID, or the default rate if they aren't on the roster.
+        match ros.get(id) {
+            Some(em) => self.rate_for(em),
+            None => self.default_rate,
+        }
+    }
+}
+
+// ==============================================
+
+/// What a schedule costs, broken down by employee, by day and by event.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CostBreakdown {
+    pub by_emp: BTreeMap<String, f64>,
+    pub by_day: [f64; 7],
+    pub by_event: BTreeMap<String, f64>,
+    pub regular_hours: f64,
+    pub overtime_hours: f64,
+    pub total: f64,
+}
+
+impl fmt::Display for CostBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::new();
+        out.push_str("\nBy employee\n=========");
+        for (id, cost) in self.by_emp.iter() {
+            out.push_str(&format!("\n{} => {:.2}", id, cost));
+        }
+        out.push_str("\n\nBy day\n=========");
+        for (i, cost) in self.by_day.iter().enumerate() {
+            out.push_str(&format!("\n{} => {:.2}", Day::from_index(i).unwrap(), cost));
+        }
+        if !self.by_event.is_empty() {
+            out.push_str("\n\nBy event\n=========");
+            for (name, cost) in self.by_event.iter() {
+                out.push_str(&format!("\n{} => {:.2}", name, cost));
+            }
+        }
+        out.push_str(&format!(
+            "\n\nRegular hours: {:.2}\nOvertime hours: {:.2}\nTotal: {:.2}",
+            self.regular_hours, self.overtime_hours, self.total
+        ));
+        write!(f, "{}", out)
+    }
+}
+
+// ==============================================
+
+/// How one shift is paid, in quarter-hours.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct PaidTime {
+    pub unpaid: usize,
+    pub regular: usize,
+    pub overtime: usize,
+    /// The sum of the pay multipliers over the overtime quarter-hours.
+    pub premium: f64,
+}
+
+// ==============================================
+
+impl Schedule {
+    pub(crate) fn paid_time(&self, id: &str) -> Vec<(Day, &Shift, PaidTime)> {
+        //! Split each of an employee's shifts, in order through the week, into unpaid break, regular time and
+        //! overtime.
+        let wages = self.wages();
+        let limit = wages.overtime_after() * 4;
+        let daily = self.daily_overtime();
+        let mut out = Vec::new();
+        // Quarter-hours already paid as daily overtime don't count towards weekly overtime as well.
+        let mut worked = 0;
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            let mut mine: Vec<_> = self
+                .shifts(&day)
+                .iter()
+                .filter(|sh| sh.emp_id == id)
+                .collect();
+            mine.sort_by_key(|sh| sh.start.get_qi());
+            let mut today = 0;
+            for sh in mine {
+                let mut paid = PaidTime {
+                    unpaid: self.break_quarters(sh),
+                    ..PaidTime::default()
+                };
+                for _ in paid.unpaid..sh.len() {
+                    match daily {
+                        Some((after, multiplier)) if today >= after * 4 => {
+                            paid.overtime += 1;
+                            paid.premium += multiplier;
+                        }
+                        _ if worked >= limit => {
+                            paid.overtime += 1;
+                            paid.premium += wages.overtime_multiplier();
+                        }
+                        _ => {
+                            paid.regular += 1;
+                            worked += 1;
+                        }
+                    }
+                    today += 1;
+                }
+                out.push((day.clone(), sh, paid));
+            }
+        }
+        out
+    }
+    fn add_employee_cost(&self, id: &str, ros: &Roster, out: &mut CostBreakdown) {
+        let rate = self.wages().rate_of(id, ros);
+        for (day, sh, paid) in self.paid_time(id) {
+            let cost = rate * (paid.regular as f64 + paid.premium) / 4.0;
+            *out.by_emp.entry(id.to_string()).or_insert(0.0) += cost;
+            out.by_day[day.to_index()] += cost;
+            if let Some(name) = &sh.event {
+                *out.by_event.entry(name.clone()).or_insert(0.0) += cost;
+            }
+            out.regular_hours += paid.regular as f64 / 4.0;
+            out.overtime_hours += paid.overtime as f64 / 4.0;
+            out.total += cost;
+        }
+    }
+    pub fn employee_cost(&self, id: &str, ros: &Roster) -> f64 {
+        //! Return what one employee's shifts on this schedule cost, overtime included.
+        let mut out = CostBreakdown::default();
+        self.add_employee_cost(id, ros, &mut out);
+        out.total
+    }
+    pub fn cost(&self, ros: &Roster) -> CostBreakdown {
+        //! Price every shift on this schedule.
+        let mut ids: Vec<String> = Vec::new();
+        for i in 0..7 {
+            for sh in self.shifts(&Day::from_index(i).unwrap()) {
+                if !ids.contains(&sh.emp_id) {
+                    ids.push(sh.emp_id.clone());
+                }
+            }
+        }
+        let mut out = CostBreakdown::default();
+        for id in ids.iter() {
+            self.add_employee_cost(id, ros, &mut out);
+        }
+        out
+    }
+    pub fn within_budget(&self, ros: &Roster) -> bool {
+        //! Check this schedule's labor cost against the weekly budget, if one is set.
+        match self.wages().budget() {
+            Some(budget) => self.cost(ros).total <= budget,
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    #[test]
+    fn rates() {
+        let mut w = Wages::new();
+        w.set_default_rate(10.0).set_role_rate("Manager", 20.0);
+        let mut em = Employee::new("Matt".to_string());
+        assert_eq!(w.rate_for(&em), 10.0);
+        em.add_role("Manager");
+        assert_eq!(w.rate_for(&em), 20.0);
+        em.set_rate(Some(15.0));
+        assert_eq!(w.rate_for(&em), 15.0);
+    }
+    #[test]
+    fn overtime() {
+        let (mut sched, ros) = Fixture::new()
+            .employee("Matt", |em| em.set_rate(Some(10.0)))
+            .shift("Matt", Day::Saturday, "9:00", "15:00")
+            .shift("Matt", Day::Sunday, "9:00", "13:00")
+            .build();
+        sched
+            .wages_mut()
+            .set_overtime(8, 2.0)
+            .set_budget(Some(150.0));
+        let cost = sched.cost(&ros);
+        assert_eq!(cost.regular_hours, 8.0);
+        assert_eq!(cost.overtime_hours, 2.0);
+        assert_eq!(cost.by_day[1], 60.0);
+        assert_eq!(cost.total, 120.0);
+        assert!(sched.within_budget(&ros));
+        sched.wages_mut().set_budget(Some(100.0));
+        assert!(!sched.within_budget(&ros));
+    }
+}
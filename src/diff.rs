@@ -0,0 +1,421 @@
+//! The diff module compares two schedules for a week, listing each employee's shift changes and where coverage
+//! of the staffing requirements got better or worse.
+use super::time::{Day, Schedule, Shift, Time};
+#[cfg(feature = "project")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// ==============================================
+
+/// One change to an employee's shifts.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub enum Change {
+    /// A shift that is new in the later schedule.
+    Added { day: Day, shift: Shift },
+    /// A shift that is gone from the later schedule.
+    Removed { day: Day, shift: Shift },
+    /// A shift that was changed, or moved to another day.
+    Moved {
+        from_day: Day,
+        from: Shift,
+        to_day: Day,
+        to: Shift,
+    },
+}
+
+impl Change {
+    fn sort_key(&self) -> (usize, usize) {
+        let (day, sh) = match self {
+            Change::Added { day, shift } | Change::Removed { day, shift } => (day, shift),
+            Change::Moved { from_day, from, .. } => (from_day, from),
+        };
+        (day.to_index(), sh.start.get_qi())
+    }
+}
+
+fn describe(day: &Day, sh: &Shift) -> String {
+    let mut out = format!("{} {}-{}", day, sh.start, sh.end);
+    if let Some(ev) = &sh.event {
+        out.push_str(&format!(" at {}", ev));
+    } else if let Some(pos) = &sh.position {
+        out.push_str(&format!(" on {}", pos));
+    }
+    out
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added { day, shift } => write!(f, "added {}", describe(day, shift)),
+            Change::Removed { day, shift } => write!(f, "removed {}", describe(day, shift)),
+            Change::Moved {
+                from_day,
+                from,
+                to_day,
+                to,
+            } => write!(
+                f,
+                "moved {} to {}",
+                describe(from_day, from),
+                describe(to_day, to)
+            ),
+        }
+    }
+}
+
+// ==============================================
+
+/// How one employee's week changed: their shift changes and scheduled hours before and after.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub struct EmployeeDiff {
+    pub emp_id: String,
+    pub changes: Vec<Change>,
+    pub hours_before: f64,
+    pub hours_after: f64,
+}
+
+impl fmt::Display for EmployeeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:.2} -> {:.2} hours ({:+.2})",
+            self.emp_id,
+            self.hours_before,
+            self.hours_after,
+            self.hours_after - self.hours_before
+        )?;
+        for change in self.changes.iter() {
+            write!(f, "\n  {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// A run of quarter-hours, `start` up to `end`, where staff on hand less staff required went from `before` to
+/// `after`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub struct CoverageChange {
+    pub day: Day,
+    pub start: usize,
+    pub end: usize,
+    pub before: i32,
+    pub after: i32,
+}
+
+fn clock(qi: usize) -> String {
+    if qi >= 24 * 4 {
+        "midnight".to_string()
+    } else {
+        Time::from_qi(qi).to_string()
+    }
+}
+
+fn standing(diff: i32) -> String {
+    match diff {
+        d if d < 0 => format!("short {}", -d),
+        0 => "met".to_string(),
+        d => format!("over {}", d),
+    }
+}
+
+impl fmt::Display for CoverageChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}-{}: {} -> {}",
+            self.day,
+            clock(self.start),
+            clock(self.end),
+            standing(self.before),
+            standing(self.after)
+        )
+    }
+}
+
+// ==============================================
+
+/// Everything that differs between two schedules for a week.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub struct ScheduleDiff {
+    pub employees: Vec<EmployeeDiff>,
+    pub coverage: Vec<CoverageChange>,
+}
+
+impl ScheduleDiff {
+    pub fn is_empty(&self) -> bool {
+        self.employees.is_empty() && self.coverage.is_empty()
+    }
+}
+
+impl fmt::Display for ScheduleDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No changes.");
+        }
+        let mut parts: Vec<String> = self.employees.iter().map(|e| e.to_string()).collect();
+        if !self.coverage.is_empty() {
+            let mut cover = "Coverage".to_string();
+            for change in self.coverage.iter() {
+                cover.push_str(&format!("\n  {}", change));
+            }
+            parts.push(cover);
+        }
+        write!(f, "{}", parts.join("\n"))
+    }
+}
+
+// ==============================================
+
+fn hours(shifts: &[(Day, &Shift)]) -> f64 {
+    shifts.iter().map(|(_, sh)| sh.len()).sum::<usize>() as f64 / 4.0
+}
+
+fn overlap(a: &Shift, b: &Shift) -> usize {
+    let (start, end) = (
+        a.start.get_qi().max(b.start.get_qi()),
+        a.end.get_qi().min(b.end.get_qi()),
+    );
+    end.saturating_sub(start)
+}
+
+fn pair_off<F>(
+    old: &mut Vec<(Day, &Shift)>,
+    new: &mut Vec<(Day, &Shift)>,
+    out: &mut Vec<Change>,
+    score: F,
+) where
+    F: Fn(&Day, &Shift, &Day, &Shift) -> Option<usize>,
+{
+    //! Match each old shift to the new shift it scores highest with, earliest first on ties, as a move.
+    old.retain(|(from_day, from)| {
+        let mut best: Option<(usize, usize)> = None;
+        for (i, (to_day, to)) in new.iter().enumerate() {
+            if let Some(points) = score(from_day, from, to_day, to) {
+                if best.is_none_or(|(most, _)| points > most) {
+                    best = Some((points, i));
+                }
+            }
+        }
+        match best {
+            Some((_, i)) => {
+                let (to_day, to) = new.remove(i);
+                out.push(Change::Moved {
+                    from_day: from_day.clone(),
+                    from: (*from).clone(),
+                    to_day,
+                    to: to.clone(),
+                });
+                false
+            }
+            None => true,
+        }
+    });
+}
+
+fn changes(mut old: Vec<(Day, &Shift)>, mut new: Vec<(Day, &Shift)>) -> Vec<Change> {
+    //! Match one employee's shifts from before and after: identical shifts are unchanged, a leftover shift is
+    //! moved to the most overlapping leftover shift on its day, or else to one of the same length on another
+    //! day, and anything still unmatched was removed or added.
+    old.retain(
+        |(day, sh)| match new.iter().position(|(d, s)| d == day && s == sh) {
+            Some(i) => {
+                new.remove(i);
+                false
+            }
+            None => true,
+        },
+    );
+    let mut out = Vec::new();
+    pair_off(&mut old, &mut new, &mut out, |fd, f, td, t| {
+        if fd == td {
+            Some(overlap(f, t))
+        } else {
+            None
+        }
+    });
+    pair_off(&mut old, &mut new, &mut out, |_, f, _, t| {
+        if f.len() == t.len() {
+            Some(0)
+        } else {
+            None
+        }
+    });
+    for (day, sh) in old {
+        out.push(Change::Removed {
+            day,
+            shift: sh.clone(),
+        });
+    }
+    for (day, sh) in new {
+        out.push(Change::Added {
+            day,
+            shift: sh.clone(),
+        });
+    }
+    out.sort_by_key(Change::sort_key);
+    out
+}
+
+impl Schedule {
+    // Comparison
+    pub fn diff(&self, after: &Schedule) -> ScheduleDiff {
+        //! Compare this schedule with a later version of it: each employee's added, removed and moved shifts
+        //! with their hours before and after, and every stretch of the week where coverage against the staffing
+        //! requirements changed. Each schedule's coverage is measured against its own requirements.
+        let mut ids: Vec<&str> = (0..7)
+            .map(|i| Day::from_index(i).unwrap())
+            .flat_map(|day| {
+                self.shifts(&day)
+                    .iter()
+                    .chain(after.shifts(&day).iter())
+                    .map(|sh| sh.emp_id.as_str())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        ids.sort();
+        ids.dedup();
+        let mut out = ScheduleDiff::default();
+        for id in ids {
+            let (old, new) = (self.shifts_of(id), after.shifts_of(id));
+            let (hours_before, hours_after) = (hours(&old), hours(&new));
+            let changes = changes(old, new);
+            if !changes.is_empty() {
+                out.employees.push(EmployeeDiff {
+                    emp_id: id.to_string(),
+                    changes,
+                    hours_before,
+                    hours_after,
+                });
+            }
+        }
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            let (have_before, have_after) =
+                (self.coverage(day.clone()), after.coverage(day.clone()));
+            let mut run: Option<CoverageChange> = None;
+            for qi in 0..=24 * 4 {
+                let gaps = if qi < 24 * 4 {
+                    Some((
+                        have_before[qi] - self.get_req(&day, qi),
+                        have_after[qi] - after.get_req(&day, qi),
+                    ))
+                } else {
+                    None
+                };
+                if let Some(mut change) = run.take() {
+                    if gaps == Some((change.before, change.after)) {
+                        change.end = qi + 1;
+                        run = Some(change);
+                        continue;
+                    }
+                    out.coverage.push(change);
+                }
+                if let Some((before, after)) = gaps.filter(|(b, a)| b != a) {
+                    run = Some(CoverageChange {
+                        day: day.clone(),
+                        start: qi,
+                        end: qi + 1,
+                        before,
+                        after,
+                    });
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    #[test]
+    fn employee_changes() {
+        let before = Fixture::new()
+            .shift("Kim", Day::Saturday, "9:00", "17:00")
+            .shift("Kim", Day::Sunday, "10:00", "14:00")
+            .shift("Lou", Day::Monday, "12:00", "18:00")
+            .shift("Sam", Day::Tuesday, "12:00", "18:00")
+            .schedule();
+        let after = Fixture::new()
+            .shift("Kim", Day::Saturday, "9:00", "17:00")
+            .shift("Kim", Day::Sunday, "12:00", "18:00")
+            .shift("Lou", Day::Wednesday, "10:00", "16:00")
+            .shift("Lou", Day::Thursday, "10:00", "12:00")
+            .shift("Sam", Day::Tuesday, "12:00", "18:00")
+            .schedule();
+        let diff = before.diff(&after);
+        assert_eq!(diff.employees.len(), 2);
+        let kim = &diff.employees[0];
+        assert_eq!((kim.hours_before, kim.hours_after), (12.0, 14.0));
+        assert_eq!(kim.changes.len(), 1);
+        match &kim.changes[0] {
+            Change::Moved { from, to, .. } => {
+                assert_eq!((from.start.get_qi(), to.start.get_qi()), (40, 48));
+            }
+            other => panic!("expected a move, got {:?}", other),
+        }
+        // Six hours on Monday moved to Wednesday; Thursday's two hours are new.
+        let lou = &diff.employees[1];
+        assert!(matches!(
+            &lou.changes[..],
+            [
+                Change::Moved {
+                    from_day: Day::Monday,
+                    to_day: Day::Wednesday,
+                    ..
+                },
+                Change::Added {
+                    day: Day::Thursday,
+                    ..
+                }
+            ]
+        ));
+        assert!(before.diff(&before).is_empty());
+        assert_eq!(before.diff(&before).to_string(), "No changes.");
+    }
+    #[test]
+    fn coverage_and_text() {
+        let store = || Fixture::new().need(Day::Saturday, "9:00", "13:00", 1);
+        let before = store()
+            .shift("Kim", Day::Saturday, "9:00", "13:00")
+            .schedule();
+        let after = store()
+            .shift("Kim", Day::Saturday, "11:00", "15:00")
+            .schedule();
+        let diff = before.diff(&after);
+        assert_eq!(
+            diff.coverage,
+            vec![
+                CoverageChange {
+                    day: Day::Saturday,
+                    start: 36,
+                    end: 44,
+                    before: 0,
+                    after: -1,
+                },
+                CoverageChange {
+                    day: Day::Saturday,
+                    start: 52,
+                    end: 60,
+                    before: 0,
+                    after: 1,
+                },
+            ]
+        );
+        let text = diff.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "Kim: 4.00 -> 4.00 hours (+0.00)");
+        assert_eq!(
+            lines[1],
+            "  moved Saturday 9:00a-1:00p to Saturday 11:00a-3:00p"
+        );
+        assert_eq!(lines[2], "Coverage");
+        assert_eq!(lines[3], "  Saturday 9:00a-11:00a: met -> short 1");
+        assert_eq!(lines[4], "  Saturday 1:00p-3:00p: met -> over 1");
+    }
+}
@@ -1,5 +1,10 @@
 //! The emp module contains tools and structures for managing employees and the full store roster thereof.
-use super::time;
+use super::pair::Pairing;
+use super::time::{self, Window};
+use super::train::Training;
+use chrono::{Datelike, NaiveDate};
+#[cfg(feature = "project")]
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 
@@ -8,27 +13,50 @@ use std::fmt;
 /// The full roster of working employees of the store.
 ///
 /// `self.emps` contains the raw HashMap of ID Strings -> Employees.
-#[derive(Debug)]
+/// `self.pairs` holds the rules about who must or mustn't work together.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
 pub struct Roster {
+    #[cfg_attr(feature = "project", serde(serialize_with = "crate::project::sorted"))]
     emps: HashMap<String, Employee>,
+    pairs: Vec<Pairing>,
+}
+
+impl Default for Roster {
+    fn default() -> Self {
+        Roster::new()
+    }
 }
 
 impl Roster {
     pub fn new() -> Roster {
         //! Create a new employee roster.
         let emps = HashMap::new();
-        Roster { emps }
+        Roster {
+            emps,
+            pairs: Vec::new(),
+        }
     }
     pub fn add(&mut self, emp: Employee) {
         //! Add an employee to the roster.
         self.emps.insert(emp.id(), emp);
     }
-    pub fn get(&self, id: String) -> &Employee {
-        //! Get an employee reference from the roster by String ID.
-        // Todo fix this nonsense, see issue #16
-        self.emps.get(&id).unwrap()
+    pub fn get(&self, id: &str) -> Option<&Employee> {
+        //! Get an employee reference from the roster by ID, if they are on it.
+        self.emps.get(id)
+    }
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Employee> {
+        //! Get a mutable employee reference from the roster by ID, if they are on it.
+        self.emps.get_mut(id)
+    }
+    pub fn add_pairing(&mut self, pairing: Pairing) {
+        //! Add a rule that two employees must, or must not, work together.
+        self.pairs.push(pairing);
     }
-    pub fn iter(&self) -> std::collections::hash_map::Iter<String, Employee> {
+    pub fn pairings(&self) -> &[Pairing] {
+        &self.pairs
+    }
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, String, Employee> {
         //! Return an iterator across the employees in this roster.
         self.emps.iter()
     }
@@ -44,7 +72,7 @@ impl fmt::Display for Roster {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut out = String::new();
         for (_s, emp) in self.iter() {
-            out.push_str("\n");
+            out.push('\n');
             out.push_str(&emp.to_string());
         }
         write!(f, "{}", out)
@@ -53,7 +81,8 @@ impl fmt::Display for Roster {
 
 //==============================================
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
 pub struct Hours(usize, usize);
 
 impl Hours {
@@ -80,14 +109,166 @@ impl Hours {
 
 //==============================================
 
-#[derive(Clone, Debug)]
+/// Something an employee would like from their schedule, without it being a hard requirement.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub enum Preference {
+    /// Working on this day.
+    Day(time::Day),
+    /// Working only within this window, whatever the day.
+    Times(Window),
+    /// Opening the store at least once.
+    Opening,
+    /// Closing the store at least once.
+    Closing,
+    /// Working shifts between these lengths, in hours.
+    ShiftLength(usize, usize),
+    /// Working no more than this many days in the week.
+    MaxDays(usize),
+}
+
+impl fmt::Display for Preference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Preference::Day(d) => write!(f, "works {}", d),
+            Preference::Times(w) => write!(f, "works {}", w),
+            Preference::Opening => write!(f, "opens"),
+            Preference::Closing => write!(f, "closes"),
+            Preference::ShiftLength(min, max) => write!(f, "{}-{} hour shifts", min, max),
+            Preference::MaxDays(n) => write!(f, "at most {} days", n),
+        }
+    }
+}
+
+//==============================================
+
+/// Where a time-off request stands in the approval workflow.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub enum Status {
+    Pending,
+    Approved,
+    Denied,
+}
+
+impl Status {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Status> {
+        match s {
+            "Pending" => Some(Status::Pending),
+            "Approved" => Some(Status::Approved),
+            "Denied" => Some(Status::Denied),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Status::Pending => "Pending",
+            Status::Approved => "Approved",
+            Status::Denied => "Denied",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A request for time off covering every day from `first` through `last`, or only `window` on each of those days.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub struct TimeOff {
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+    pub window: Option<Window>,
+    pub reason: String,
+    status: Status,
+}
+
+impl TimeOff {
+    // Constructors
+    pub fn new(first: NaiveDate, last: NaiveDate) -> TimeOff {
+        //! Create a new pending request for whole days off from `first` through `last`.
+        TimeOff {
+            first,
+            last,
+            window: None,
+            reason: String::new(),
+            status: Status::Pending,
+        }
+    }
+    pub fn partial(date: NaiveDate, window: Window) -> TimeOff {
+        //! Create a new pending request for part of a single day off, e.g. for an appointment.
+        TimeOff {
+            window: Some(window),
+            ..TimeOff::new(date, date)
+        }
+    }
+    // Workflow
+    pub fn approve(&mut self) {
+        self.status = Status::Approved;
+    }
+    pub fn deny(&mut self) {
+        self.status = Status::Denied;
+    }
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+    pub fn is_approved(&self) -> bool {
+        self.status == Status::Approved
+    }
+    // Access
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        //! Check if this request includes any time on `date`, whatever its status.
+        self.first <= date && date <= self.last
+    }
+    pub fn blocks(&self, date: NaiveDate, start: &time::Time, end: &time::Time) -> bool {
+        //! Check if this request is approved and takes away any of the span from `start` to `end` on `date`.
+        self.is_approved()
+            && self.covers(date)
+            && match &self.window {
+                Some(w) => w.overlaps(start, end),
+                None => true,
+            }
+    }
+}
+
+impl fmt::Display for TimeOff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = if self.first == self.last {
+            format!("{}", self.first)
+        } else {
+            format!("{} - {}", self.first, self.last)
+        };
+        if let Some(w) = &self.window {
+            out.push_str(&format!(" ({})", w));
+        }
+        out.push_str(&format!(" [{}]", self.status));
+        if !self.reason.is_empty() {
+            out.push_str(&format!(" {}", self.reason));
+        }
+        write!(f, "{}", out)
+    }
+}
+
+//==============================================
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
 /// An employee of the business, identified by the String `self.id`.
 pub struct Employee {
     pub iden: String,
-    avail: [bool; 7],
+    #[cfg_attr(feature = "project", serde(with = "crate::project::avail"))]
+    avail: [Vec<Window>; 7],
     hrs: Hours,
+    dob: Option<NaiveDate>,
+    rate: Option<f64>,
+    #[cfg_attr(feature = "project", serde(serialize_with = "crate::project::sorted"))]
     abils: HashMap<String, u8>,
     roles: Vec<String>,
+    time_off: Vec<TimeOff>,
+    prefs: Vec<(Preference, f64)>,
+    training: Vec<Training>,
 }
 
 impl Employee {
@@ -96,10 +277,23 @@ impl Employee {
         //! Create a new Employee.
         Employee {
             iden,
-            avail: [true; 7],
+            avail: [
+                vec![Window::all_day()],
+                vec![Window::all_day()],
+                vec![Window::all_day()],
+                vec![Window::all_day()],
+                vec![Window::all_day()],
+                vec![Window::all_day()],
+                vec![Window::all_day()],
+            ],
             hrs: Hours(38, 40),
+            dob: None,
+            rate: None,
             abils: HashMap::new(),
             roles: Vec::new(),
+            time_off: Vec::new(),
+            prefs: Vec::new(),
+            training: Vec::new(),
         }
     }
     // self.iden
@@ -109,15 +303,99 @@ impl Employee {
     }
     // self.avail
     pub fn set_available(&mut self, day: time::Day, b: bool) -> bool {
-        //! Set an employee's availability for a day. Returns the previous availability for that day.
+        //! Set an employee's availability for a whole day. Returns the previous availability for that day.
         let d_i = day.to_index();
-        let out = self.avail[d_i];
-        self.avail[d_i] = b;
+        let out = !self.avail[d_i].is_empty();
+        self.avail[d_i] = if b {
+            vec![Window::all_day()]
+        } else {
+            Vec::new()
+        };
         out
     }
+    pub fn set_windows(&mut self, day: time::Day, windows: Vec<Window>) {
+        //! Replace an employee's availability for a day with a set of time windows.
+        self.avail[day.to_index()] = windows;
+    }
+    pub fn add_window(&mut self, day: time::Day, window: Window) {
+        //! Add a time window during which this employee can work on a day.
+        self.avail[day.to_index()].push(window);
+    }
+    pub fn windows(&self, day: &time::Day) -> &[Window] {
+        //! Return the time windows during which this employee can work on a day.
+        &self.avail[day.to_index()]
+    }
     pub fn is_available(&self, day: time::Day) -> bool {
-        //! Check an if this employee can work a day.
+        //! Check an if this employee can work at any point during a day.
+        !self.avail[day.to_index()].is_empty()
+    }
+    pub fn is_available_for(&self, day: &time::Day, start: &time::Time, end: &time::Time) -> bool {
+        //! Check if this employee can work the whole span from `start` to `end` on a day.
         self.avail[day.to_index()]
+            .iter()
+            .any(|w| w.contains(start, end))
+    }
+    pub fn available_windows(&self, day: &time::Day, date: Option<NaiveDate>) -> Vec<Window> {
+        //! Return the windows this employee can work on a day, less any approved time off if the date is known.
+        let mut out = self.avail[day.to_index()].clone();
+        if let Some(date) = date {
+            for req in self.time_off.iter() {
+                if !req.is_approved() || !req.covers(date) {
+                    continue;
+                }
+                out = match &req.window {
+                    Some(off) => out.iter().flat_map(|w| w.minus(off)).collect(),
+                    None => Vec::new(),
+                };
+            }
+        }
+        out
+    }
+    pub fn can_work(
+        &self,
+        day: &time::Day,
+        date: Option<NaiveDate>,
+        start: &time::Time,
+        end: &time::Time,
+    ) -> bool {
+        //! Check if this employee can work the whole span from `start` to `end`, honouring approved time off.
+        self.available_windows(day, date)
+            .iter()
+            .any(|w| w.contains(start, end))
+    }
+    // self.time_off
+    pub fn request_time_off(&mut self, req: TimeOff) -> usize {
+        //! Record a time-off request. Returns its index, for use with `time_off_mut`.
+        self.time_off.push(req);
+        self.time_off.len() - 1
+    }
+    pub fn time_off(&self) -> &[TimeOff] {
+        &self.time_off
+    }
+    pub fn time_off_mut(&mut self, i: usize) -> Option<&mut TimeOff> {
+        //! Access a time-off request by index, e.g. to approve or deny it.
+        self.time_off.get_mut(i)
+    }
+    pub fn is_on_leave(&self, date: NaiveDate, start: &time::Time, end: &time::Time) -> bool {
+        //! Check if any approved time off takes away part of the span from `start` to `end` on `date`.
+        self.time_off.iter().any(|req| req.blocks(date, start, end))
+    }
+    // self.prefs
+    pub fn add_preference(&mut self, pref: Preference, weight: f64) {
+        //! Record a soft preference, weighted by how much it should count when scoring a schedule.
+        self.prefs.push((pref, weight));
+    }
+    pub fn preferences(&self) -> &[(Preference, f64)] {
+        //! Return this employee's soft preferences alongside their weights.
+        &self.prefs
+    }
+    // self.training
+    pub fn add_training(&mut self, rule: Training) {
+        //! Require someone able to train this employee to work alongside them, as `rule` describes.
+        self.training.push(rule);
+    }
+    pub fn training(&self) -> &[Training] {
+        &self.training
     }
     // self.hrs
     pub fn get_hours(&self) -> &Hours {
@@ -126,33 +404,49 @@ impl Employee {
     pub fn hours(&mut self) -> &mut Hours {
         &mut self.hrs
     }
+    // self.dob
+    pub fn set_dob(&mut self, dob: Option<NaiveDate>) {
+        //! Set (or clear) this employee's date of birth.
+        self.dob = dob;
+    }
+    pub fn dob(&self) -> Option<NaiveDate> {
+        self.dob
+    }
+    pub fn age_on(&self, date: NaiveDate) -> Option<u32> {
+        //! Return this employee's age in whole years on a date, if their date of birth is known.
+        let dob = self.dob?;
+        let mut age = date.year() - dob.year();
+        if (date.month(), date.day()) < (dob.month(), dob.day()) {
+            age -= 1;
+        }
+        Some(age.max(0) as u32)
+    }
+    // self.rate
+    pub fn set_rate(&mut self, rate: Option<f64>) {
+        //! Set (or clear) this employee's own hourly rate, which takes precedence over any role rate.
+        self.rate = rate;
+    }
+    pub fn rate(&self) -> Option<f64> {
+        self.rate
+    }
     // self.abils
     pub fn set_abil(&mut self, k: &str, v: u8) {
-        let key = k.clone();
         if let Some(val) = self.abils.insert(k.to_owned(), v) {
-            println!("Updated {} to {} in {}", key, val, self.id());
+            println!("Updated {} to {} in {}", k, val, self.id());
         };
     }
     pub fn get_abil(&self, k: &str) -> Option<u8> {
-        if let Some(u) = self.abils.get(k) {
-            Some(*u)
-        } else {
-            None
-        }
+        self.abils.get(k).copied()
     }
     pub fn is_able(&self, k: &str) -> bool {
-        if let Some(_) = self.abils.get(k) {
-            true
-        } else {
-            false
-        }
+        self.abils.contains_key(k)
     }
     // self.roles
     pub fn add_role(&mut self, s: &str) {
         self.roles.push(s.to_owned());
     }
     pub fn remove_role(&mut self, s: &str) {
-        for (_i, role) in self.roles.iter().enumerate() {
+        for role in self.roles.iter() {
             if role == s {
                 // self.roles.remove(i);
                 println!(
@@ -163,6 +457,9 @@ impl Employee {
             }
         }
     }
+    pub fn roles(&self) -> &[String] {
+        &self.roles
+    }
     pub fn has_role(&self, s: &str) -> bool {
         for role in self.roles.iter() {
             if role == s {
@@ -180,8 +477,12 @@ impl fmt::Display for Employee {
         let weekdays = ["Sat", "Sun", "Mon", "Tue", "Wed", "Thu", "Fri"];
         out.push_str("\nCan work: \n");
         for (n, name) in weekdays.iter().enumerate() {
-            if self.avail[n] {
-                out.push_str(&format!("{} ", name));
+            for window in self.avail[n].iter() {
+                if *window == Window::all_day() {
+                    out.push_str(&format!("{} ", name));
+                } else {
+                    out.push_str(&format!("{} ({}) ", name, window));
+                }
             }
         }
         out.push_str(&format!(
@@ -189,6 +490,24 @@ impl fmt::Display for Employee {
             self.get_hours().min(),
             self.get_hours().max()
         ));
+        if !self.prefs.is_empty() {
+            out.push_str("\nPrefers:");
+            for (pref, weight) in self.prefs.iter() {
+                out.push_str(&format!("\n  {} ({})", pref, weight));
+            }
+        }
+        if !self.training.is_empty() {
+            out.push_str("\nTraining:");
+            for rule in self.training.iter() {
+                out.push_str(&format!("\n  {}", rule));
+            }
+        }
+        if !self.time_off.is_empty() {
+            out.push_str("\nTime off:");
+            for req in self.time_off.iter() {
+                out.push_str(&format!("\n  {}", req));
+            }
+        }
         write!(f, "{}", out)
     }
 }
@@ -0,0 +1,107 @@
+//! The fixture module builds the rosters and schedules shared by the unit tests.
+use super::emp::{Employee, Roster};
+use super::pair::Pairing;
+use super::time::{Day, Schedule, Time};
+use chrono::NaiveDate;
+
+// ==============================================
+
+/// The week test schedules are anchored to: Saturday, June 8th 2019.
+pub fn week() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2019, 6, 8).unwrap()
+}
+
+/// A roster and schedule under construction.
+///
+/// Staff are added available all week with 0 to 40 hours, so only what a test sets up
+/// itself can cause problems.
+pub struct Fixture {
+    ros: Roster,
+    sched: Schedule,
+}
+
+impl Fixture {
+    // Constructor
+    pub fn new() -> Fixture {
+        //! Start with an empty roster and an empty, unanchored schedule.
+        Fixture {
+            ros: Roster::new(),
+            sched: Schedule::new(),
+        }
+    }
+
+    // Modification
+    pub fn staff(mut self, ids: &[&str]) -> Fixture {
+        //! Add an employee for each of `ids`.
+        for id in ids {
+            let mut em = Employee::new(id.to_string());
+            em.hours().set(0, 40);
+            self.ros.add(em);
+        }
+        self
+    }
+    pub fn employee(mut self, id: &str, f: impl FnOnce(&mut Employee)) -> Fixture {
+        //! Add the employee `id`, then let `f` give them anything else they need.
+        self = self.staff(&[id]);
+        f(self.ros.get_mut(id).unwrap());
+        self
+    }
+    pub fn pairing(mut self, pairing: Pairing) -> Fixture {
+        //! Add `pairing` to the roster.
+        self.ros.add_pairing(pairing);
+        self
+    }
+    pub fn dated(mut self) -> Fixture {
+        //! Anchor the schedule to `week()`.
+        self.sched.set_week(week());
+        self
+    }
+    pub fn need(mut self, day: Day, start: &str, end: &str, num_emps: i32) -> Fixture {
+        //! Require `num_emps` staff on `day` from `start` until `end`.
+        for qi in Time::from_str(start).get_qi()..Time::from_str(end).get_qi() {
+            self.sched.set_req(&day, qi, num_emps);
+        }
+        self
+    }
+    pub fn shift(mut self, id: &str, day: Day, start: &str, end: &str) -> Fixture {
+        //! Give `id` a shift on `day` from `start` until `end`.
+        self.sched.assign_shift(
+            id.to_string(),
+            day,
+            Time::from_str(start),
+            Time::from_str(end),
+        );
+        self
+    }
+    pub fn event(mut self, name: &str, kind: &str, day: Day, start: &str, end: &str) -> Fixture {
+        //! Add an event with no setup or breakdown time.
+        self.sched
+            .add_event(name, kind, day, Time::from_str(start), Time::from_str(end));
+        self
+    }
+    pub fn prep(mut self, setup: usize, breakdown: usize) -> Fixture {
+        //! Give the last event added `setup` and `breakdown` quarter hours either side.
+        self.sched
+            .events
+            .last_mut()
+            .unwrap()
+            .setup_breakdown(setup, breakdown);
+        self
+    }
+    pub fn run(mut self, id: &str) -> Fixture {
+        //! Assign `id` to run the last event added.
+        let ev = self.sched.events.last().unwrap().clone();
+        self.sched.assign_event(id.to_string(), ev);
+        self
+    }
+
+    // Export
+    pub fn schedule(self) -> Schedule {
+        //! Finish, keeping only the schedule.
+        self.sched
+    }
+    pub fn build(self) -> (Schedule, Roster) {
+        //! Finish, keeping the schedule and roster.
+        (self.sched, self.ros)
+    }
+}
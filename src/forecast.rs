@@ -0,0 +1,201 @@
+//! The forecast module turns historical point-of-sale traffic into staffing requirements.
+use super::time::{Day, Schedule, Time};
+use std::fmt;
+
+// ==============================================
+
+/// One hour of point-of-sale history: the number of transactions rung up in the hour beginning at `hour` on `day`.
+#[derive(Clone, Debug)]
+pub struct Traffic {
+    pub day: Day,
+    pub hour: Time,
+    pub transactions: f64,
+}
+
+// ==============================================
+
+/// The staffing ratio used to turn traffic into headcount, e.g. "1 associate per 12 transactions/hour".
+#[derive(Clone, Debug)]
+pub struct StaffRatio {
+    per_emp: f64,
+    min_emps: i32,
+}
+
+impl StaffRatio {
+    // Constructor
+    pub fn new(per_emp: f64) -> StaffRatio {
+        //! Create a ratio of one employee per `per_emp` transactions per hour, never staffing below one employee.
+        StaffRatio {
+            per_emp,
+            min_emps: 1,
+        }
+    }
+    // Modification
+    pub fn set_min(&mut self, min_emps: i32) -> &mut StaffRatio {
+        //! Set the smallest headcount this ratio will ever ask for, however quiet the store is.
+        self.min_emps = min_emps;
+        self
+    }
+    // Access
+    pub fn per_emp(&self) -> f64 {
+        self.per_emp
+    }
+    pub fn min(&self) -> i32 {
+        self.min_emps
+    }
+    pub fn staff_for(&self, transactions: f64) -> i32 {
+        //! Return the number of employees needed to handle `transactions` per hour.
+        //!
+        //! # Examples
+        //! ```
+        //! use makeshift::forecast::StaffRatio;
+        //! let ratio = StaffRatio::new(12.0);
+        //! assert_eq!(ratio.staff_for(0.0), 1);
+        //! assert_eq!(ratio.staff_for(24.0), 2);
+        //! assert_eq!(ratio.staff_for(25.0), 3);
+        //! ```
+        let needed = (transactions / self.per_emp).ceil() as i32;
+        needed.max(self.min_emps)
+    }
+}
+
+// ==============================================
+
+/// A weekly staffing-demand profile learned from `Traffic` history.
+///
+/// Each weekday and quarter-hour slot holds the average transactions/hour seen across every sample for that slot.
+#[derive(Clone)]
+pub struct Profile {
+    totals: [[f64; 24 * 4]; 7],
+    samples: [[u32; 24 * 4]; 7],
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::new()
+    }
+}
+
+impl Profile {
+    // Constructors
+    pub fn new() -> Profile {
+        //! Create a new, empty demand profile.
+        Profile {
+            totals: [[0.0; 24 * 4]; 7],
+            samples: [[0; 24 * 4]; 7],
+        }
+    }
+    pub fn from_history(history: &[Traffic]) -> Profile {
+        //! Learn a demand profile from a full traffic history.
+        let mut out = Profile::new();
+        for t in history {
+            out.learn(t);
+        }
+        out
+    }
+    // Modification
+    pub fn learn(&mut self, t: &Traffic) {
+        //! Add one hour of traffic to this profile.
+        let d_i = t.day.to_index();
+        let start = t.hour.get_qi();
+        let end = (start + 4).min(24 * 4);
+        for qi in start..end {
+            self.totals[d_i][qi] += t.transactions;
+            self.samples[d_i][qi] += 1;
+        }
+    }
+    // Access
+    pub fn demand(&self, day: &Day, qi: usize) -> Option<f64> {
+        //! Return the average transactions/hour for a slot, or None if no history covers it.
+        let d_i = day.to_index();
+        match self.samples[d_i][qi] {
+            0 => None,
+            n => Some(self.totals[d_i][qi] / f64::from(n)),
+        }
+    }
+    pub fn apply(&self, sched: &mut Schedule, ratio: &StaffRatio) {
+        //! Overwrite a schedule's staffing requirements with the headcount this profile calls for.
+        //!
+        //! Only slots during the store's open hours (see `Schedule::set_hours`) which have history are changed, so
+        //! closed hours stay closed, the staff before opening and after closing are left alone, and slots with no
+        //! traffic data keep their existing requirement. Days without open hours are left as they are.
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            let (open, close) = match sched.open_hours(&day) {
+                Some(hours) => hours,
+                None => continue,
+            };
+            for qi in open..close {
+                if let Some(tx) = self.demand(&day, qi) {
+                    sched.set_req(&day, qi, ratio.staff_for(tx));
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::new();
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            out.push_str(&format!("\n{}\n=========", day));
+            for hour in 0..24 {
+                if let Some(tx) = self.demand(&day, hour * 4) {
+                    out.push_str(&format!("\n{: <6} {:.1}", Time::from_hour(hour), tx));
+                }
+            }
+        }
+        write!(f, "{}", out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn tx(day: Day, hour: usize, transactions: f64) -> Traffic {
+        Traffic {
+            day,
+            hour: Time::from_hour(hour),
+            transactions,
+        }
+    }
+    #[test]
+    fn averages_weeks() {
+        let p = Profile::from_history(&[tx(Day::Saturday, 12, 20.0), tx(Day::Saturday, 12, 40.0)]);
+        assert_eq!(p.demand(&Day::Saturday, 48), Some(30.0));
+        assert_eq!(p.demand(&Day::Saturday, 51), Some(30.0));
+        assert_eq!(p.demand(&Day::Saturday, 52), None);
+        assert_eq!(p.demand(&Day::Sunday, 48), None);
+    }
+    #[test]
+    fn ratio_min() {
+        let mut r = StaffRatio::new(10.0);
+        r.set_min(2);
+        assert_eq!(r.staff_for(5.0), 2);
+        assert_eq!(r.staff_for(31.0), 4);
+    }
+    #[test]
+    fn apply_open_hours_only() {
+        let mut sched = Schedule::new();
+        sched.set_hours(Day::Saturday, 10, 12);
+        let p = Profile::from_history(&[
+            tx(Day::Saturday, 9, 36.0),
+            tx(Day::Saturday, 11, 36.0),
+            tx(Day::Saturday, 12, 36.0),
+            tx(Day::Saturday, 20, 36.0),
+            tx(Day::Sunday, 11, 36.0),
+        ]);
+        sched.set_req(&Day::Sunday, 44, 1);
+        p.apply(&mut sched, &StaffRatio::new(12.0));
+        assert_eq!(sched.get_req(&Day::Saturday, 44), 3);
+        assert_eq!(sched.get_req(&Day::Saturday, 40), 4);
+        assert_eq!(sched.get_req(&Day::Saturday, 80), 0);
+        // The slot before opening and the slots after closing keep what set_hours gave them.
+        assert_eq!(sched.get_req(&Day::Saturday, 39), 3);
+        assert_eq!(sched.get_req(&Day::Saturday, 49), 3);
+        // Sunday has requirements but no open hours, so it is left alone.
+        assert_eq!(sched.get_req(&Day::Sunday, 44), 1);
+        assert_eq!(sched.get_req(&Day::Sunday, 45), 0);
+    }
+}
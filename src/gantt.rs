@@ -0,0 +1,167 @@
+//! The gantt module draws a schedule as a text timeline for quick checks in the terminal.
+use super::time::{Day, Schedule};
+
+/// Marks a quarter-hour of a regular shift.
+pub const SHIFT: char = '█';
+/// Marks a quarter-hour spent on an event, setup and breakdown included.
+pub const EVENT: char = '▒';
+/// Marks a quarter-hour off the clock.
+pub const OFF: char = '·';
+
+// ==============================================
+
+/// A labelled row of one character per quarter-hour.
+struct Line {
+    label: String,
+    marks: Vec<char>,
+}
+
+fn digit(n: i32) -> char {
+    //! Return a count as a single character, with '+' standing in for ten or more.
+    match n {
+        n if n <= 0 => ' ',
+        n if n > 9 => '+',
+        n => std::char::from_digit(n as u32, 10).unwrap(),
+    }
+}
+
+fn render(lines: &[Line], lo: usize, hi: usize, width: usize) -> String {
+    //! Lay out rows covering quarter-hours `lo` to `hi`, wrapping whole hours onto further blocks if they
+    //! won't fit in `width` columns.
+    let label_w = lines
+        .iter()
+        .map(|l| l.label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let per_block = (width.saturating_sub(label_w + 1) / 4 * 4).max(4);
+    let mut out = String::new();
+    let mut start = lo;
+    while start < hi {
+        let end = (start + per_block).min(hi);
+        let mut header = vec![' '; end - start];
+        for qi in (start..end).step_by(4) {
+            for (i, c) in (qi / 4).to_string().chars().enumerate() {
+                if qi - start + i < header.len() {
+                    header[qi - start + i] = c;
+                }
+            }
+        }
+        if start > lo {
+            out.push('\n');
+        }
+        let header: String = header.into_iter().collect();
+        let mut rows = vec![format!("{:w$} {}", "", header, w = label_w)];
+        for line in lines {
+            let marks: String = line.marks[start - lo..end - lo].iter().collect();
+            rows.push(format!("{:w$} {}", line.label, marks, w = label_w));
+        }
+        for row in rows {
+            out.push_str(row.trim_end());
+            out.push('\n');
+        }
+        start = end;
+    }
+    out
+}
+
+// ==============================================
+
+impl Schedule {
+    // Display
+    pub fn gantt(&self, day: &Day, width: usize) -> String {
+        //! Draw one day as a row per employee and a column per quarter-hour, with rows along the bottom for
+        //! staff on hand and how many short of the requirement they fall, wrapped to fit `width` columns.
+        let (lo, hi) = match self.timeline(day) {
+            Some(span) => span,
+            None => return format!("{}: nothing scheduled\n", day),
+        };
+        let mut ids: Vec<&str> = self
+            .shifts(day)
+            .iter()
+            .map(|sh| sh.emp_id.as_str())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        let mut lines = Vec::new();
+        for id in ids {
+            let mut marks = vec![OFF; hi - lo];
+            for sh in self.shifts(day).iter().filter(|sh| sh.emp_id == id) {
+                let mark = if sh.event.is_some() { EVENT } else { SHIFT };
+                for m in marks[sh.start.get_qi() - lo..sh.end.get_qi() - lo].iter_mut() {
+                    *m = mark;
+                }
+            }
+            lines.push(Line {
+                label: id.to_string(),
+                marks,
+            });
+        }
+        let cover = self.coverage(day.clone());
+        lines.push(Line {
+            label: "on".to_string(),
+            marks: (lo..hi).map(|qi| digit(cover[qi])).collect(),
+        });
+        lines.push(Line {
+            label: "short".to_string(),
+            marks: (lo..hi)
+                .map(|qi| digit(self.get_req(day, qi) - cover[qi]))
+                .collect(),
+        });
+        format!("{}\n{}", day, render(&lines, lo, hi, width))
+    }
+    pub fn gantt_week(&self, width: usize) -> String {
+        //! Draw every day of the week that has anything on it, one after another.
+        let days: Vec<String> = (0..7)
+            .map(|i| Day::from_index(i).unwrap())
+            .filter(|day| self.timeline(day).is_some())
+            .map(|day| self.gantt(&day, width))
+            .collect();
+        days.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::time::Time;
+    fn setup() -> Schedule {
+        Fixture::new()
+            .need(Day::Saturday, "10:00", "12:00", 2)
+            .shift("Kim", Day::Saturday, "10:00", "12:00")
+            .event("League", "Pokemon", Day::Saturday, "10:30", "11:00")
+            .prep(2, 0)
+            .run("Lou")
+            .schedule()
+    }
+    #[test]
+    fn one_day() {
+        let sched = setup();
+        let chart = sched.gantt(&Day::Saturday, 80);
+        let lines: Vec<&str> = chart.lines().collect();
+        assert_eq!(lines[0], "Saturday");
+        assert_eq!(lines[1], "      10  11");
+        assert_eq!(lines[2], "Kim   ████████");
+        assert_eq!(lines[3], "Lou   ▒▒▒▒····");
+        // Lou's event needs its own staff, so only Kim counts as on hand.
+        assert_eq!(lines[4], "on    11111111");
+        assert_eq!(lines[5], "short 11111111");
+        assert_eq!(sched.gantt(&Day::Sunday, 80), "Sunday: nothing scheduled\n");
+    }
+    #[test]
+    fn wraps_whole_hours() {
+        let mut sched = Schedule::new();
+        sched.assign_shift(
+            "Kim".to_string(),
+            Day::Saturday,
+            Time::from_hour(9),
+            Time::from_hour(21),
+        );
+        let chart = sched.gantt_week(30);
+        assert!(chart.lines().all(|l| l.chars().count() <= 30));
+        // 12 hours at 6 hours (24 columns) per block.
+        assert_eq!(chart.matches("\nKim ").count(), 2);
+        assert!(chart.contains("\n\n      15  16"));
+        assert!(chart.contains("\nshort\n"));
+    }
+}
@@ -0,0 +1,289 @@
+//! The gen module fills a schedule's coverage gaps with shifts chosen to balance coverage, preferences and cost.
+use super::emp::Roster;
+use super::time::{Day, Schedule, Shift, Time};
+use super::valid::{self, Problem};
+
+// ==============================================
+
+/// What the generator aims for when choosing between candidate shifts.
+///
+/// Each candidate is scored as `coverage` per quarter-hour of shortfall it covers, plus `preferences` times the
+/// preference weight it gains its employee, less `cost` times the labor cost it adds. Shift lengths are in hours.
+#[derive(Clone, Debug)]
+pub struct Objective {
+    pub coverage: f64,
+    pub preferences: f64,
+    pub cost: f64,
+    pub lengths: Vec<usize>,
+}
+
+impl Default for Objective {
+    fn default() -> Self {
+        Objective {
+            coverage: 1.0,
+            preferences: 1.0,
+            cost: 0.0,
+            lengths: vec![8, 6, 4],
+        }
+    }
+}
+
+impl Objective {
+    pub fn with_cost(cost: f64) -> Objective {
+        //! Create the default objective, also weighing each unit of labor cost against `cost` of the others.
+        Objective {
+            cost,
+            ..Objective::default()
+        }
+    }
+}
+
+// ==============================================
+
+/// Check if a problem can't be fixed by adding more shifts, and so must never be made worse by doing so.
+pub(crate) fn is_hard(p: &Problem) -> bool {
+    match p {
+        Problem::Coverage { .. }
+        | Problem::PositionCoverage { .. }
+        | Problem::NoKeys { .. }
+        | Problem::Undated { .. } => false,
+        Problem::Hours { assigned, max, .. } => assigned > max,
+        _ => true,
+    }
+}
+
+pub(crate) fn tolerates<'a>(p: &Problem, before: impl IntoIterator<Item = &'a Problem>) -> bool {
+    //! Check if adding a shift may leave the hard problem `p`, because it was already broken and is no worse.
+    //! Once the week is over budget, gaps are still covered whatever they cost.
+    before.into_iter().any(|was| match (p, was) {
+        (Problem::OverBudget { .. }, Problem::OverBudget { .. }) => true,
+        _ => valid::worsens(p, was) == Some(false),
+    })
+}
+
+fn hard_problems(sched: &Schedule, ros: &Roster) -> Vec<Problem> {
+    sched.problems(ros).into_iter().filter(is_hard).collect()
+}
+
+impl Schedule {
+    fn shortfall_runs(&self, day: &Day) -> Vec<(usize, usize)> {
+        let coverage = self.coverage(day.clone());
+        let mut out = Vec::new();
+        let mut start = None;
+        for (qi, cover) in coverage.iter().enumerate() {
+            let short = *cover < self.get_req(day, qi);
+            match (short, start) {
+                (true, None) => start = Some(qi),
+                (false, Some(s)) => {
+                    out.push((s, qi));
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+        if let Some(s) = start {
+            out.push((s, 24 * 4));
+        }
+        out
+    }
+    fn preference_weight(&self, id: &str, ros: &Roster) -> f64 {
+        match ros.iter().find(|(k, _)| *k == id) {
+            Some((_, em)) => em
+                .preferences()
+                .iter()
+                .filter(|(pref, _)| self.preference_met(id, pref))
+                .map(|(_, weight)| weight)
+                .sum(),
+            None => 0.0,
+        }
+    }
+    fn candidate_value(
+        &mut self,
+        day: &Day,
+        sh: Shift,
+        ros: &Roster,
+        obj: &Objective,
+    ) -> Option<f64> {
+        let coverage = self.coverage(day.clone());
+        let mut covered = (sh.start.get_qi()..sh.end.get_qi())
+            .filter(|qi| coverage[*qi] < self.get_req(day, *qi))
+            .count();
+        // Opening or closing with the keys counts the same as covering an hour's shortfall.
+        covered += 4 * self.key_value(day, &sh.emp_id, sh.start.get_qi(), sh.end.get_qi(), ros);
+        // Quarter-hours in which a trainee would gain a trainer count the same as covering a shortfall.
+        if let Some(em) = ros.get(&sh.emp_id) {
+            covered += self
+                .training_gaps(day, ros)
+                .iter()
+                .filter(|gap| gap.trainee != sh.emp_id && gap.rule.is_trainer(em))
+                .flat_map(|gap| gap.quarters.iter())
+                .filter(|qi| sh.start.get_qi() <= **qi && **qi < sh.end.get_qi())
+                .count();
+        }
+        // So do quarter-hours in which a position the employee can take lacks anyone able to take it.
+        if let Some(em) = ros.get(&sh.emp_id) {
+            let mut gaps: Vec<usize> = self
+                .position_gaps(day, ros)
+                .into_iter()
+                .filter(|(_, qi)| sh.start.get_qi() <= *qi && *qi < sh.end.get_qi())
+                .filter(|(name, _)| self.position(name).is_some_and(|p| p.is_eligible(em)))
+                .map(|(_, qi)| qi)
+                .collect();
+            gaps.sort_unstable();
+            gaps.dedup();
+            covered += gaps.len();
+        }
+        if covered == 0 {
+            return None;
+        }
+        let id = sh.emp_id.clone();
+        let prefs_before = self.preference_weight(&id, ros);
+        let cost_before = self.employee_cost(&id, ros);
+        self.shifts[day.to_index()].push(sh);
+        let prefs = self.preference_weight(&id, ros) - prefs_before;
+        let cost = self.employee_cost(&id, ros) - cost_before;
+        self.shifts[day.to_index()].pop();
+        Some(obj.coverage * covered as f64 + obj.preferences * prefs - obj.cost * cost)
+    }
+    pub(crate) fn candidates(&mut self, ros: &Roster, obj: &Objective) -> Vec<(f64, Day, Shift)> {
+        let mut ids: Vec<&String> = ros.iter().map(|(id, _)| id).collect();
+        ids.sort();
+        let mut out = Vec::new();
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            let date = self.date_of(&day);
+            let (open, close) = match (self.opening(&day), self.closing(&day)) {
+                (Some(o), Some(c)) => (o, c.min(24 * 4 - 1)),
+                _ => continue,
+            };
+            let mut runs = self.shortfall_runs(&day);
+            for (_, qi) in self.position_gaps(&day, ros) {
+                match runs.iter_mut().find(|(_, b)| *b == qi) {
+                    Some(run) => run.1 = qi + 1,
+                    None => runs.push((qi, qi + 1)),
+                }
+            }
+            let mut spans: Vec<(usize, usize)> = self
+                .training_gaps(&day, ros)
+                .iter()
+                .map(|gap| (gap.start, gap.end))
+                .collect();
+            for len in obj.lengths.iter() {
+                let len = (len * 4).min(close - open);
+                for (a, b) in runs.iter() {
+                    for start in [*a, b.saturating_sub(len)].iter() {
+                        let start = (*start).max(open).min(close - len);
+                        if !spans.contains(&(start, start + len)) {
+                            spans.push((start, start + len));
+                        }
+                    }
+                }
+            }
+            for id in ids.iter() {
+                let em = ros.get(id).unwrap();
+                if self.shifts[i].iter().any(|sh| sh.emp_id == **id) {
+                    continue;
+                }
+                let worked: usize = self.shifts_of(id).iter().map(|(_, sh)| sh.len()).sum();
+                for (s, e) in spans.iter() {
+                    let (start, end) = (Time::from_qi(*s), Time::from_qi(*e));
+                    if worked + (e - s) > em.get_hours().max() * 4
+                        || !em.can_work(&day, date, &start, &end)
+                    {
+                        continue;
+                    }
+                    let sh = Shift::new(id.to_string(), start, end);
+                    if let Some(value) = self.candidate_value(&day, sh.clone(), ros, obj) {
+                        if value > 0.0 {
+                            out.push((value, day.clone(), sh));
+                        }
+                    }
+                }
+            }
+        }
+        out.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        out
+    }
+    pub fn fill_shifts(&mut self, ros: &Roster, obj: &Objective) -> usize {
+        //! Greedily add the best-scoring shift that breaks no new rules until coverage is met or nothing helps.
+        //! Returns the number of shifts added.
+        let mut added = 0;
+        loop {
+            let before = hard_problems(self, ros);
+            let mut placed = 0;
+            for (_value, day, sh) in self.candidates(ros, obj) {
+                // Anyone who must work together with the candidate is placed alongside them.
+                let group = self.with_partners(&sh, ros);
+                let day_shifts = &mut self.shifts[day.to_index()];
+                let len = day_shifts.len();
+                day_shifts.extend(group);
+                if hard_problems(self, ros)
+                    .iter()
+                    .all(|p| tolerates(p, &before))
+                {
+                    placed = self.shifts[day.to_index()].len() - len;
+                    break;
+                }
+                self.shifts[day.to_index()].truncate(len);
+            }
+            if placed == 0 {
+                break;
+            }
+            added += placed;
+        }
+        added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::time::Window;
+    fn setup() -> (Schedule, Roster) {
+        Fixture::new()
+            .employee("Ann", |em| em.set_rate(Some(20.0)))
+            .employee("Bob", |em| em.set_rate(Some(10.0)))
+            .need(Day::Saturday, "10:00", "14:00", 1)
+            .build()
+    }
+    #[test]
+    fn fills_coverage() {
+        let (mut sched, ros) = setup();
+        assert_eq!(sched.fill_shifts(&ros, &Objective::default()), 1);
+        assert!(sched.coverage_problems().is_empty());
+    }
+    #[test]
+    fn cheapest() {
+        let (mut sched, ros) = setup();
+        sched.fill_shifts(&ros, &Objective::with_cost(0.1));
+        assert_eq!(sched.shifts(&Day::Saturday)[0].emp_id, "Bob");
+    }
+    #[test]
+    fn respects_availability() {
+        let (mut sched, mut ros) = setup();
+        ros.get_mut("Bob").unwrap().set_windows(
+            Day::Saturday,
+            vec![Window::from_str("16:00-20:00").unwrap()],
+        );
+        sched.fill_shifts(&ros, &Objective::with_cost(0.1));
+        assert_eq!(sched.shifts(&Day::Saturday)[0].emp_id, "Ann");
+    }
+    #[test]
+    fn budget() {
+        let (mut sched, ros) = setup();
+        sched.assign_shift(
+            "Ann".to_string(),
+            Day::Sunday,
+            Time::from_hour(10),
+            Time::from_hour(14),
+        );
+        // Bob's 40.00 would take the week past the budget.
+        sched.wages_mut().set_budget(Some(100.0));
+        assert_eq!(sched.fill_shifts(&ros, &Objective::default()), 0);
+        // Once it's overspent anyway, gaps are still covered.
+        sched.wages_mut().set_budget(Some(50.0));
+        assert_eq!(sched.fill_shifts(&ros, &Objective::default()), 1);
+        assert!(sched.coverage_problems().is_empty());
+    }
+}
@@ -0,0 +1,332 @@
+//! The grid module lays out a week's schedule the way managers print it, and exports it as CSV or XLSX.
+use super::emp::Roster;
+use super::time::{Day, Schedule, Time};
+use std::error::Error;
+use std::fs::File;
+use std::io::{Seek, Write};
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+// ==============================================
+
+/// A table of text cells with a header row, as exported to a spreadsheet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Grid {
+    // Access
+    pub fn cell(&self, row: usize, col: usize) -> Option<&str> {
+        //! Return the text of a cell below the header, if there is one.
+        self.rows.get(row)?.get(col).map(|s| s.as_str())
+    }
+    // Export
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), Box<dyn Error>> {
+        let mut wtr = csv::Writer::from_writer(writer);
+        wtr.write_record(&self.header)?;
+        for row in self.rows.iter() {
+            wtr.write_record(row)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+    fn sheet_xml(&self) -> String {
+        //! Return this grid as SpreadsheetML, writing cells below the header that hold numbers as numbers. The first
+        //! column labels each row, so is always text, as is anything that wouldn't read back exactly as written,
+        //! such as "007" or "NaN".
+        let mut out = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+            "<sheetData>"
+        ));
+        for (r, row) in Some(&self.header)
+            .into_iter()
+            .chain(self.rows.iter())
+            .enumerate()
+        {
+            out.push_str(&format!(r#"<row r="{}">"#, r + 1));
+            for (c, text) in row.iter().enumerate() {
+                let at = format!("{}{}", column_name(c), r + 1);
+                if text.is_empty() {
+                    continue;
+                }
+                match text.parse::<f64>() {
+                    Ok(n) if r > 0 && c > 0 && n.is_finite() && n.to_string() == *text => {
+                        out.push_str(&format!(r#"<c r="{}"><v>{}</v></c>"#, at, n))
+                    }
+                    _ => out.push_str(&format!(
+                        r#"<c r="{}" t="inlineStr"><is><t>{}</t></is></c>"#,
+                        at,
+                        escape(text)
+                    )),
+                }
+            }
+            out.push_str("</row>");
+        }
+        out.push_str("</sheetData></worksheet>");
+        out
+    }
+}
+
+fn column_name(mut col: usize) -> String {
+    //! Return the spreadsheet letters for a 0-indexed column: A, B, ... Z, AA, AB, ...
+    let mut out = Vec::new();
+    loop {
+        out.push((b'A' + (col % 26) as u8) as char);
+        if col < 26 {
+            break;
+        }
+        col = col / 26 - 1;
+    }
+    out.iter().rev().collect()
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn hours(quarters: usize) -> String {
+    format!("{}", quarters as f64 / 4.0)
+}
+
+// ==============================================
+
+/// Write a workbook with one worksheet per named grid.
+pub fn write_xlsx<W: Write + Seek>(
+    writer: W,
+    sheets: &[(&str, &Grid)],
+) -> Result<(), Box<dyn Error>> {
+    let mut zip = ZipWriter::new(writer);
+    let opts = FileOptions::default();
+    let mut types = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+        r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+        r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+        r#"<Override PartName="/xl/workbook.xml" "#,
+        r#"ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#
+    ));
+    let mut book = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" "#,
+        r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>"#
+    ));
+    let mut rels = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#
+    ));
+    for (i, (name, _)) in sheets.iter().enumerate() {
+        let n = i + 1;
+        types.push_str(&format!(
+            r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="{}"/>"#,
+            n, "application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"
+        ));
+        book.push_str(&format!(
+            r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#,
+            escape(name),
+            n,
+            n
+        ));
+        rels.push_str(&format!(
+            r#"<Relationship Id="rId{}" Type="{}" Target="worksheets/sheet{}.xml"/>"#,
+            n, "http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet", n
+        ));
+    }
+    types.push_str("</Types>");
+    book.push_str("</sheets></workbook>");
+    rels.push_str("</Relationships>");
+    zip.start_file("[Content_Types].xml", opts)?;
+    zip.write_all(types.as_bytes())?;
+    zip.start_file("_rels/.rels", opts)?;
+    zip.write_all(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+            r#"<Relationship Id="rId1" "#,
+            r#"Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" "#,
+            r#"Target="xl/workbook.xml"/></Relationships>"#
+        )
+        .as_bytes(),
+    )?;
+    zip.start_file("xl/workbook.xml", opts)?;
+    zip.write_all(book.as_bytes())?;
+    zip.start_file("xl/_rels/workbook.xml.rels", opts)?;
+    zip.write_all(rels.as_bytes())?;
+    for (i, (_, grid)) in sheets.iter().enumerate() {
+        zip.start_file(format!("xl/worksheets/sheet{}.xml", i + 1), opts)?;
+        zip.write_all(grid.sheet_xml().as_bytes())?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+// ==============================================
+
+impl Schedule {
+    // Layout
+    pub fn grid(&self, ros: &Roster) -> Grid {
+        //! Lay out the week with employees as rows and days as columns, each cell listing that day's shifts,
+        //! followed by weekly hours for each employee and a row of daily totals.
+        let mut header = vec!["Employee".to_string()];
+        header.extend((0..7).map(|i| Day::from_index(i).unwrap().to_string()));
+        header.push("Hours".to_string());
+        let mut ids: Vec<String> = ros.iter().map(|(id, _)| id.clone()).collect();
+        for i in 0..7 {
+            for sh in self.shifts(&Day::from_index(i).unwrap()) {
+                if !ids.contains(&sh.emp_id) {
+                    ids.push(sh.emp_id.clone());
+                }
+            }
+        }
+        ids.sort();
+        let mut daily = [0; 7];
+        let mut rows = Vec::new();
+        for id in ids {
+            let mut row = vec![id.clone()];
+            let mut total = 0;
+            for (i, day_total) in daily.iter_mut().enumerate() {
+                let mut shifts: Vec<_> = self
+                    .shifts(&Day::from_index(i).unwrap())
+                    .iter()
+                    .filter(|sh| sh.emp_id == id)
+                    .collect();
+                shifts.sort_by_key(|sh| sh.start.get_qi());
+                let cells: Vec<String> = shifts
+                    .iter()
+                    .map(|sh| match &sh.event {
+                        Some(ev) => format!("{}-{} {}", sh.start, sh.end, ev),
+                        None => format!("{}-{}", sh.start, sh.end),
+                    })
+                    .collect();
+                let worked: usize = shifts.iter().map(|sh| sh.len()).sum();
+                total += worked;
+                *day_total += worked;
+                row.push(cells.join("; "));
+            }
+            row.push(hours(total));
+            rows.push(row);
+        }
+        let mut totals = vec!["Total".to_string()];
+        totals.extend(daily.iter().map(|q| hours(*q)));
+        totals.push(hours(daily.iter().sum()));
+        rows.push(totals);
+        Grid { header, rows }
+    }
+    pub fn coverage_grid(&self) -> Grid {
+        //! Lay out staff on hand against staff required for every quarter-hour of the week that needs or has staff,
+        //! with two columns per day.
+        let mut header = vec!["Time".to_string()];
+        let mut have = Vec::new();
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            header.push(format!("{} staff", day));
+            header.push(format!("{} needed", day));
+            have.push(self.coverage(day));
+        }
+        let mut rows = Vec::new();
+        for qi in 0..96 {
+            let slot: Vec<(i32, i32)> = have
+                .iter()
+                .enumerate()
+                .map(|(i, day)| (day[qi], self.get_req(&Day::from_index(i).unwrap(), qi)))
+                .collect();
+            if slot.iter().all(|&(h, n)| h == 0 && n == 0) {
+                continue;
+            }
+            let mut row = vec![Time::from_qi(qi).to_string()];
+            for (h, n) in slot {
+                row.push(h.to_string());
+                row.push(n.to_string());
+            }
+            rows.push(row);
+        }
+        Grid { header, rows }
+    }
+    // Export
+    pub fn write_grid_csv(&self, path: &Path, ros: &Roster) -> Result<(), Box<dyn Error>> {
+        //! Write the weekly grid to a CSV file.
+        self.grid(ros).write_csv(File::create(path)?)
+    }
+    pub fn write_grid_xlsx(&self, path: &Path, ros: &Roster) -> Result<(), Box<dyn Error>> {
+        //! Write the weekly grid to an XLSX workbook, with a second sheet of coverage against requirements.
+        write_xlsx(
+            File::create(path)?,
+            &[
+                ("Schedule", &self.grid(ros)),
+                ("Coverage", &self.coverage_grid()),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use std::io::{Cursor, Read};
+    fn setup() -> (Schedule, Roster) {
+        Fixture::new()
+            .staff(&["Kim", "Lou"])
+            .need(Day::Saturday, "10:00", "10:15", 1)
+            .shift("Kim", Day::Saturday, "10:00", "14:00")
+            .shift("Kim", Day::Saturday, "16:00", "18:30")
+            .build()
+    }
+    #[test]
+    fn weekly_grid() {
+        let (sched, ros) = setup();
+        let grid = sched.grid(&ros);
+        assert_eq!(grid.header.len(), 9);
+        assert_eq!(grid.cell(0, 0), Some("Kim"));
+        assert_eq!(grid.cell(0, 1), Some("10:00a-2:00p; 4:00p-6:30p"));
+        assert_eq!(grid.cell(0, 8), Some("6.5"));
+        assert_eq!(grid.cell(1, 8), Some("0"));
+        assert_eq!(grid.cell(2, 0), Some("Total"));
+        let mut csv = Vec::new();
+        grid.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("Employee,Saturday,Sunday,"));
+        assert!(csv.contains("Kim,10:00a-2:00p; 4:00p-6:30p,,,,,,,6.5\n"));
+    }
+    #[test]
+    fn workbook() {
+        let (sched, ros) = setup();
+        let coverage = sched.coverage_grid();
+        assert_eq!(coverage.rows.len(), 4 * 4 + 10);
+        assert_eq!(coverage.cell(0, 1), Some("1"));
+        assert_eq!(coverage.cell(0, 2), Some("1"));
+        let mut buf = Cursor::new(Vec::new());
+        write_xlsx(
+            &mut buf,
+            &[("Schedule", &sched.grid(&ros)), ("Coverage", &coverage)],
+        )
+        .unwrap();
+        let mut zip = zip::ZipArchive::new(buf).unwrap();
+        let mut sheet = String::new();
+        zip.by_name("xl/worksheets/sheet1.xml")
+            .unwrap()
+            .read_to_string(&mut sheet)
+            .unwrap();
+        assert!(sheet
+            .contains(r#"<c r="B2" t="inlineStr"><is><t>10:00a-2:00p; 4:00p-6:30p</t></is></c>"#));
+        assert!(sheet.contains(r#"<c r="I2"><v>6.5</v></c>"#));
+        assert!(zip.by_name("xl/worksheets/sheet2.xml").is_ok());
+        assert!(zip.by_name("[Content_Types].xml").is_ok());
+        assert_eq!(column_name(27), "AB");
+        // Names that look like numbers stay as written.
+        let (sched, ros) = Fixture::new()
+            .staff(&["007", "NaN"])
+            .shift("007", Day::Saturday, "10:00", "14:00")
+            .build();
+        let sheet = sched.grid(&ros).sheet_xml();
+        assert!(sheet.contains(r#"<c r="A2" t="inlineStr"><is><t>007</t></is></c>"#));
+        assert!(sheet.contains(r#"<c r="A3" t="inlineStr"><is><t>NaN</t></is></c>"#));
+        assert!(sheet.contains(r#"<c r="I2"><v>4</v></c>"#));
+    }
+}
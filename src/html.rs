@@ -0,0 +1,239 @@
+//! The html module renders a week's schedule as a self-contained, printable HTML page of daily timelines.
+use super::grid::escape;
+use super::time::{Day, Schedule, Time};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+const STYLE: &str = "
+body { font-family: sans-serif; font-size: 10pt; margin: 0 auto; max-width: 60em; }
+h1 { font-size: 16pt; }
+h2 { font-size: 12pt; margin: 1em 0 0.25em; }
+.day { break-inside: avoid; page-break-inside: avoid; margin-bottom: 1em; }
+.row { display: flex; align-items: center; height: 1.4em; border-bottom: 1px solid #ddd; }
+.label { width: 8em; flex: none; overflow: hidden; white-space: nowrap; text-overflow: ellipsis; }
+.track { position: relative; flex: 1; height: 100%; }
+.bar, .part { position: absolute; top: 0.2em; bottom: 0.2em; overflow: hidden; white-space: nowrap; font-size: 8pt; }
+.bar { background: #7aa6d8; border: 1px solid #3b6ea5; }
+.event { background: #e0b44c; border: 1px solid #a57b1b; }
+.prep { background: repeating-linear-gradient(45deg, #f3dca4, #f3dca4 3px, #e0b44c 3px, #e0b44c 6px); }
+.heat { display: flex; height: 100%; }
+.heat span { flex: 1; }
+.ticks span { position: absolute; font-size: 7pt; color: #555; }
+.legend span { display: inline-block; padding: 0 0.5em; margin-right: 0.5em; }
+* { -webkit-print-color-adjust: exact; print-color-adjust: exact; }
+@page { margin: 12mm; }
+@media print { body { max-width: none; } }
+";
+
+// ==============================================
+
+fn color(diff: i32) -> &'static str {
+    //! Shade a quarter-hour by staff on hand less staff required, so shortfalls stand out.
+    match diff {
+        d if d <= -2 => "#d73027",
+        -1 => "#fc8d59",
+        0 => "#f7f7f7",
+        1 => "#d9ef8b",
+        _ => "#91cf60",
+    }
+}
+
+/// The quarter-hours one day's timeline spans.
+struct Span {
+    lo: usize,
+    hi: usize,
+}
+
+impl Span {
+    fn pct(&self, qi: usize) -> f64 {
+        (qi.clamp(self.lo, self.hi) - self.lo) as f64 * 100.0 / (self.hi - self.lo) as f64
+    }
+    fn block(&self, class: &str, start: usize, end: usize, text: &str, title: &str) -> String {
+        //! Draw a positioned block covering quarter-hours `start` to `end`.
+        format!(
+            r#"<div class="{}" style="left:{:.2}%;width:{:.2}%" title="{}">{}</div>"#,
+            class,
+            self.pct(start),
+            self.pct(end) - self.pct(start),
+            escape(title),
+            escape(text)
+        )
+    }
+}
+
+// ==============================================
+
+impl Schedule {
+    fn day_html(&self, day: &Day) -> String {
+        let span = match self.timeline(day) {
+            Some((lo, hi)) => Span { lo, hi },
+            None => return String::new(),
+        };
+        let mut out = format!(r#"<section class="day"><h2>{}"#, day);
+        if let Some(date) = self.date_of(day) {
+            out.push_str(&format!(" {}", date.format("%b %-d")));
+        }
+        out.push_str("</h2>");
+        // Hour ticks
+        out.push_str(r#"<div class="row"><div class="label"></div><div class="track ticks">"#);
+        for qi in (span.lo..span.hi).step_by(4) {
+            out.push_str(&format!(
+                r#"<span style="left:{:.2}%">{}</span>"#,
+                span.pct(qi),
+                Time::from_qi(qi)
+            ));
+        }
+        out.push_str("</div></div>");
+        // Events, with setup and breakdown hatched
+        for ev in self.events.iter().filter(|ev| ev.day == *day) {
+            let title = format!(
+                "{} ({}): {}-{}, {} staff",
+                ev.name, ev.kind, ev.start, ev.end, ev.num_emps
+            );
+            out.push_str(&format!(
+                r#"<div class="row"><div class="label">{}</div><div class="track">"#,
+                escape(&ev.name)
+            ));
+            let (start, end) = (ev.start.get_qi(), ev.end.get_qi());
+            let (first, last) = ev.span();
+            out.push_str(&span.block("part prep", first, start, "", "Setup"));
+            out.push_str(&span.block("part event", start, end, &ev.kind, &title));
+            out.push_str(&span.block("part prep", end, last, "", "Breakdown"));
+            out.push_str("</div></div>");
+        }
+        // One row per employee, shifts as bars
+        let mut ids: Vec<&str> = self
+            .shifts(day)
+            .iter()
+            .map(|sh| sh.emp_id.as_str())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        for id in ids {
+            out.push_str(&format!(
+                r#"<div class="row"><div class="label">{}</div><div class="track">"#,
+                escape(id)
+            ));
+            for sh in self.shifts(day).iter().filter(|sh| sh.emp_id == id) {
+                let mut text = format!("{}-{}", sh.start, sh.end);
+                if let Some(name) = sh.event.as_ref().or(sh.position.as_ref()) {
+                    text.push_str(&format!(" {}", name));
+                }
+                let class = if sh.event.is_some() {
+                    "bar event"
+                } else {
+                    "bar"
+                };
+                out.push_str(&span.block(class, sh.start.get_qi(), sh.end.get_qi(), &text, &text));
+            }
+            out.push_str("</div></div>");
+        }
+        // Coverage heatmap
+        let cover = self.coverage(day.clone());
+        out.push_str(
+            r#"<div class="row"><div class="label">Coverage</div><div class="track heat">"#,
+        );
+        for (qi, have) in cover.iter().enumerate().take(span.hi).skip(span.lo) {
+            let need = self.get_req(day, qi);
+            out.push_str(&format!(
+                r#"<span style="background:{}" title="{}: {} on, {} needed"></span>"#,
+                color(have - need),
+                Time::from_qi(qi),
+                have,
+                need
+            ));
+        }
+        out.push_str("</div></div></section>");
+        out
+    }
+    // Export
+    pub fn html_report(&self) -> String {
+        //! Return a standalone HTML page with a timeline for each day of the week: shifts drawn as bars, events
+        //! with their setup and breakdown, and a heatmap of coverage against the staffing requirements.
+        let mut title = "Weekly Schedule".to_string();
+        if let Some(week) = self.week() {
+            title.push_str(&format!(" for {}", week.format("%B %-d, %Y")));
+        }
+        let mut out = format!(
+            concat!(
+                "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title>",
+                "<style>{}</style></head><body><h1>{}</h1>"
+            ),
+            title, STYLE, title
+        );
+        out.push_str(r#"<p class="legend">Coverage: "#);
+        for (diff, text) in [
+            (-2, "short 2+"),
+            (-1, "short 1"),
+            (0, "met"),
+            (1, "over 1"),
+            (2, "over 2+"),
+        ]
+        .iter()
+        {
+            out.push_str(&format!(
+                r#"<span style="background:{}">{}</span>"#,
+                color(*diff),
+                text
+            ));
+        }
+        out.push_str("</p>");
+        let days: Vec<String> = (0..7)
+            .map(|i| self.day_html(&Day::from_index(i).unwrap()))
+            .filter(|html| !html.is_empty())
+            .collect();
+        if days.is_empty() {
+            out.push_str("<p>Nothing is scheduled this week.</p>");
+        }
+        for html in days {
+            out.push_str(&html);
+        }
+        out.push_str("</body></html>\n");
+        out
+    }
+    pub fn write_html(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        //! Write the HTML report to a file.
+        fs::write(path, self.html_report())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    fn setup() -> Schedule {
+        Fixture::new()
+            .need(Day::Saturday, "10:00", "18:00", 1)
+            .event("Kids & Cards", "Magic", Day::Saturday, "12:00", "14:00")
+            .prep(4, 4)
+            .shift("Kim", Day::Saturday, "9:45", "14:00")
+            .schedule()
+    }
+    #[test]
+    fn timelines() {
+        let sched = setup();
+        let html = sched.html_report();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h2>Saturday</h2>"));
+        assert!(!html.contains("<h2>Sunday</h2>"));
+        // Saturday runs 9:00 to 18:00, so Kim's 9:45 start is 3 of 36 quarters in.
+        assert!(html.contains(r#"<div class="bar" style="left:8.33%;width:47.22%""#));
+        assert!(html.contains("Kids &amp; Cards"));
+        assert!(html.contains(r#"title="Setup""#));
+        assert_eq!(html.matches(r#"class="part prep""#).count(), 2);
+    }
+    #[test]
+    fn heatmap_shows_gaps() {
+        let sched = setup();
+        let html = sched.html_report();
+        // Kim covers 10:00 but is needed at the event too, and nobody covers the afternoon.
+        assert!(html.contains(r#"title="10:00a: 1 on, 1 needed""#));
+        assert!(html.contains(r#"title="4:00p: 0 on, 1 needed""#));
+        assert!(html.contains(&format!(r#"background:{}" title="4:00p"#, color(-1))));
+        assert!(Schedule::new()
+            .html_report()
+            .contains("Nothing is scheduled"));
+    }
+}
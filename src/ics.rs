@@ -0,0 +1,301 @@
+//! The ics module exports schedules as iCalendar files, one feed per employee and one public calendar of events.
+use super::emp::Roster;
+use super::time::{Day, Event, Schedule, Shift};
+use chrono::{Duration, NaiveDate, Utc};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// ==============================================
+
+/// An iCalendar document under construction.
+struct Calendar {
+    lines: Vec<String>,
+    stamp: String,
+}
+
+impl Calendar {
+    fn new(name: &str) -> Calendar {
+        let mut cal = Calendar {
+            lines: Vec::new(),
+            stamp: Utc::now().format("%Y%m%dT%H%M%SZ").to_string(),
+        };
+        cal.line("BEGIN", "VCALENDAR");
+        cal.line("VERSION", "2.0");
+        cal.line("PRODID", "-//MakeShift//Schedule//EN");
+        cal.line("CALSCALE", "GREGORIAN");
+        cal.line("X-WR-CALNAME", &escape(name));
+        cal
+    }
+    fn line(&mut self, name: &str, value: &str) {
+        self.lines.push(fold(&format!("{}:{}", name, value)));
+    }
+    fn event(
+        &mut self,
+        uid: &str,
+        date: NaiveDate,
+        start: usize,
+        end: usize,
+        summary: &str,
+        desc: &str,
+    ) {
+        //! Add a VEVENT running from quarter-hour `start` to `end` on `date`, in the store's local time.
+        self.line("BEGIN", "VEVENT");
+        self.line("UID", &format!("{}@makeshift", uid));
+        let stamp = self.stamp.clone();
+        self.line("DTSTAMP", &stamp);
+        self.line("DTSTART", &local(date, start));
+        self.line("DTEND", &local(date, end));
+        self.line("SUMMARY", &escape(summary));
+        if !desc.is_empty() {
+            self.line("DESCRIPTION", &escape(desc));
+        }
+        self.line("END", "VEVENT");
+    }
+    fn finish(mut self) -> String {
+        self.line("END", "VCALENDAR");
+        let mut out = self.lines.join("\r\n");
+        out.push_str("\r\n");
+        out
+    }
+}
+
+fn local(date: NaiveDate, qi: usize) -> String {
+    //! Format a quarter-hour of a date as a floating local date-time.
+    let at = date.and_hms_opt(0, 0, 0).unwrap() + Duration::minutes(qi as i64 * 15);
+    at.format("%Y%m%dT%H%M%S").to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn uid_part(text: &str) -> String {
+    //! Encode a name for use in a UID, keeping distinct names distinct: anything but a letter or digit is written
+    //! as %XX for each of its UTF-8 bytes.
+    let mut out = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!("%{:02X}", b));
+            }
+        }
+    }
+    out
+}
+
+fn fold(line: &str) -> String {
+    //! Break a content line into pieces of at most 75 bytes, continuing each with a leading space.
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out
+}
+
+fn slug(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// ==============================================
+
+impl Schedule {
+    fn anchored(&self) -> Result<NaiveDate, Box<dyn Error>> {
+        let err = "calendars need the schedule anchored to a week with Schedule::set_week";
+        self.week().ok_or_else(|| err.into())
+    }
+    fn shift_event(&self, day: &Day, sh: &Shift) -> Option<&Event> {
+        let name = sh.event.as_ref()?;
+        self.events
+            .iter()
+            .find(|ev| ev.name == *name && ev.day == *day)
+    }
+    // Export
+    pub fn employee_calendar(&self, id: &str) -> Result<String, Box<dyn Error>> {
+        //! Return an iCalendar feed of one employee's shifts, with event assignments including setup and breakdown.
+        self.anchored()?;
+        let mut cal = Calendar::new(&format!("{} shifts", id));
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            let date = self.date_of(&day).unwrap();
+            for sh in self.shifts(&day).iter().filter(|sh| sh.emp_id == id) {
+                let (start, end) = (sh.start.get_qi(), sh.end.get_qi());
+                let uid = format!(
+                    "{}-{}-{}-{}",
+                    date.format("%Y%m%d"),
+                    start,
+                    end,
+                    uid_part(id)
+                );
+                let (summary, desc) = match self.shift_event(&day, sh) {
+                    Some(ev) => (
+                        format!("{} ({})", ev.name, ev.kind),
+                        format!(
+                            "Setup from {}, event {}-{}, breakdown until {}",
+                            sh.start, ev.start, ev.end, sh.end
+                        ),
+                    ),
+                    None => {
+                        let mut desc = String::new();
+                        if let Some(pos) = &sh.position {
+                            desc.push_str(&format!("Position: {}", pos));
+                        }
+                        for (win, pos) in sh.segments.iter() {
+                            desc.push_str(&format!("\n{}: {}", win, pos));
+                        }
+                        ("Shift".to_string(), desc.trim_start().to_string())
+                    }
+                };
+                cal.event(&uid, date, start, end, &summary, &desc);
+            }
+        }
+        Ok(cal.finish())
+    }
+    pub fn event_calendar(&self) -> Result<String, Box<dyn Error>> {
+        //! Return a public iCalendar feed of the store's events, without any staff details.
+        let week = self.anchored()?;
+        let mut cal = Calendar::new("Store events");
+        for ev in self.events.iter() {
+            let date = week + Duration::days(ev.day.to_index() as i64);
+            let (start, end) = (ev.start.get_qi(), ev.end.get_qi());
+            let uid = format!("{}-{}-{}", date.format("%Y%m%d"), start, uid_part(&ev.name));
+            cal.event(&uid, date, start, end, &ev.name, &ev.kind);
+        }
+        Ok(cal.finish())
+    }
+    pub fn write_calendars(
+        &self,
+        dir: &Path,
+        ros: &Roster,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        //! Write `events.ics` and one `<id>.ics` per rostered employee into `dir`, returning the files written.
+        //!
+        //! IDs are made safe for file names, so when two come out the same (say "Mary Ann" and "Mary_Ann"), or
+        //! one comes out as "events", the later in sorted order gets a numbered suffix such as `Mary_Ann-2.ics`.
+        self.anchored()?;
+        fs::create_dir_all(dir)?;
+        let mut out = Vec::new();
+        let events = dir.join("events.ics");
+        fs::write(&events, self.event_calendar()?)?;
+        out.push(events);
+        let mut ids: Vec<&String> = ros.iter().map(|(id, _)| id).collect();
+        ids.sort();
+        // Compared without case, for file systems that ignore it.
+        let mut taken = vec!["events".to_string()];
+        for id in ids {
+            let base = slug(id);
+            let mut name = base.clone();
+            let mut n = 1;
+            while taken.contains(&name.to_lowercase()) {
+                n += 1;
+                name = format!("{}-{}", base, n);
+            }
+            taken.push(name.to_lowercase());
+            let path = dir.join(format!("{}.ics", name));
+            fs::write(&path, self.employee_calendar(id)?)?;
+            out.push(path);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    fn setup() -> Schedule {
+        Fixture::new()
+            .dated()
+            .event("Friday Night Magic", "Magic", Day::Friday, "18:00", "22:00")
+            .prep(2, 4)
+            .run("Matt")
+            .shift("Matt", Day::Saturday, "9:45", "23:45")
+            .schedule()
+    }
+    #[test]
+    fn employee_feed() {
+        let sched = setup();
+        let ics = sched.employee_calendar("Matt").unwrap();
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("DTSTART:20190608T094500\r\n"));
+        assert!(ics.contains("DTEND:20190608T234500\r\n"));
+        // Friday's shift runs from the start of setup to the end of breakdown.
+        assert!(ics.contains("DTSTART:20190614T173000\r\n"));
+        assert!(ics.contains("DTEND:20190614T230000\r\n"));
+        assert!(ics.lines().all(|l| l.len() <= 75));
+        assert!(Schedule::new().employee_calendar("Matt").is_err());
+    }
+    #[test]
+    fn public_events() {
+        let sched = setup();
+        let ics = sched.event_calendar().unwrap();
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("SUMMARY:Friday Night Magic\r\n"));
+        assert!(ics.contains("DTSTART:20190614T180000\r\n"));
+        assert!(!ics.contains("Matt"));
+    }
+    #[test]
+    fn distinct_files() {
+        let sched = setup();
+        let (_, ros) = Fixture::new()
+            .staff(&["Mary Ann", "Mary_Ann", "events"])
+            .build();
+        let dir = std::env::temp_dir().join(format!("makeshift-ics-{}", std::process::id()));
+        let written = sched.write_calendars(&dir, &ros).unwrap();
+        let names: Vec<_> = written
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "events.ics",
+                "Mary_Ann.ics",
+                "Mary_Ann-2.ics",
+                "events-2.ics"
+            ]
+        );
+        let feed = fs::read_to_string(dir.join("Mary_Ann-2.ics")).unwrap();
+        assert!(feed.contains("X-WR-CALNAME:Mary_Ann shifts"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn distinct_uids() {
+        let sched = Fixture::new()
+            .dated()
+            .shift("Mary Ann", Day::Saturday, "9:00", "17:00")
+            .shift("Mary_Ann", Day::Saturday, "9:00", "17:00")
+            .schedule();
+        let uid = |id: &str| {
+            let ics = sched.employee_calendar(id).unwrap();
+            ics.lines()
+                .find(|l| l.starts_with("UID:"))
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(uid("Mary Ann"), "UID:20190608-36-68-Mary%20Ann@makeshift");
+        assert_eq!(uid("Mary_Ann"), "UID:20190608-36-68-Mary%5FAnn@makeshift");
+    }
+}
@@ -1,100 +1,962 @@
 //! File importing and parsing.
-use super::emp::{Employee, Roster};
-use super::time::{Day, Event, Time};
+//!
+//! Every import collects what it finds wrong into a `Report` rather than stopping at the first bad record.
+use super::emp::{Employee, Roster, Status, TimeOff};
+use super::forecast::Traffic;
+use super::time::{Day, Event, Schedule, Shift, Time, Window};
+use chrono::NaiveDate;
 use csv;
+use std::collections::HashSet;
 use std::error::Error;
+use std::fmt;
 use std::fs;
+use std::io;
+use std::str::FromStr;
 
-pub fn get_roster() -> Result<Roster, Box<Error>> {
-    let file = fs::File::open("./docs/roster.csv")?;
-    let mut rdr = csv::Reader::from_reader(file);
-    let mut ros = Roster::new();
-    let mut headers = Vec::new();
-    {
-        for item in rdr.headers()? {
-            headers.push(item.to_owned());
+// ==============================================
+
+/// How serious an import problem is. Errors drop the record they're found in, warnings keep it, and errors from
+/// `check_references` mark data that can't be scheduled as it stands.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
         }
     }
-    for result in rdr.records() {
-        let record = result?;
-        if let Ok(empl) = build_empl(&record, &headers) {
-            println!("{:#?}", empl);
-            ros.add(empl);
-        } else {
-            println!("Error reading record: {:#?}", record);
+}
+
+/// A problem found while importing, located by file, line and column where those are known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: String,
+    pub line: Option<u64>,
+    pub column: Option<String>,
+    pub value: String,
+    pub reason: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        if let Some(column) = &self.column {
+            write!(f, " [{}]", column)?;
         }
+        if !self.value.is_empty() {
+            write!(f, " \"{}\"", self.value)?;
+        }
+        write!(f, ": {}", self.reason)
     }
-    Ok(ros)
 }
 
-pub fn get_events() -> Result<Vec<Event>, Box<Error>> {
-    let file = fs::File::open("./docs/events.csv")?;
-    let mut rdr = csv::Reader::from_reader(file);
+/// Everything found wrong across one or more imports, in the order it was found.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    // Constructor
+    pub fn new() -> Report {
+        Report::default()
+    }
+    // Modification
+    pub fn push(&mut self, diag: Diagnostic) {
+        self.diagnostics.push(diag);
+    }
+    // Access
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+    }
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Warning)
+    }
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = format!(
+            "Import: {} errors, {} warnings",
+            self.errors().count(),
+            self.warnings().count()
+        );
+        for diag in self.errors().chain(self.warnings()) {
+            out.push_str(&format!("\n  {}", diag));
+        }
+        write!(f, "{}", out)
+    }
+}
+
+// ==============================================
+
+/// The columns of a CSV file, found by header name so that they may come in any order.
+///
+/// Headers match regardless of case or surrounding space; `headers` keeps them as written.
+struct Columns {
+    file: String,
+    headers: Vec<String>,
+    names: Vec<String>,
+}
+
+impl Columns {
+    fn new(
+        file: &str,
+        headers: &csv::StringRecord,
+        required: &[&str],
+        report: &mut Report,
+    ) -> Option<Columns> {
+        //! Read a file's headers, reporting any required columns that are missing.
+        let headers: Vec<String> = headers.iter().map(|h| h.trim().to_owned()).collect();
+        let names: Vec<String> = headers.iter().map(|h| h.to_lowercase()).collect();
+        let mut ok = true;
+        for r in required.iter().filter(|r| !names.iter().any(|n| n == *r)) {
+            ok = false;
+            report.push(Diagnostic {
+                severity: Severity::Error,
+                file: file.to_owned(),
+                line: Some(1),
+                column: Some(r.to_string()),
+                value: String::new(),
+                reason: "required column is missing".to_owned(),
+            });
+        }
+        if !ok {
+            return None;
+        }
+        Some(Columns {
+            file: file.to_owned(),
+            headers,
+            names,
+        })
+    }
+    fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+}
+
+/// One record being read, gathering diagnostics as its values are parsed.
+struct Row<'a> {
+    cols: &'a Columns,
+    sr: &'a csv::StringRecord,
+    diags: Vec<Diagnostic>,
+}
+
+impl<'a> Row<'a> {
+    fn new(cols: &'a Columns, sr: &'a csv::StringRecord) -> Row<'a> {
+        Row {
+            cols,
+            sr,
+            diags: Vec::new(),
+        }
+    }
+    fn text(&self, name: &str) -> &'a str {
+        //! Return the value in the named column, or "" if the column or value is absent.
+        let sr = self.sr;
+        self.cols
+            .index(name)
+            .and_then(|i| sr.get(i))
+            .unwrap_or("")
+            .trim()
+    }
+    fn line(&self) -> Option<u64> {
+        self.sr.position().map(|p| p.line())
+    }
+    fn report(&mut self, severity: Severity, name: &str, reason: &str) {
+        let value = self.text(name).to_owned();
+        let line = self.line();
+        self.diags.push(Diagnostic {
+            severity,
+            file: self.cols.file.clone(),
+            line,
+            column: Some(name.to_owned()),
+            value,
+            reason: reason.to_owned(),
+        });
+    }
+    fn error(&mut self, name: &str, reason: &str) {
+        self.report(Severity::Error, name, reason);
+    }
+    fn warn(&mut self, name: &str, reason: &str) {
+        self.report(Severity::Warning, name, reason);
+    }
+    fn required(&mut self, name: &str) -> Option<&'a str> {
+        let value = self.text(name);
+        if value.is_empty() {
+            self.error(name, "a value is required");
+            return None;
+        }
+        Some(value)
+    }
+    fn parse<T: FromStr>(&mut self, name: &str, what: &str) -> Option<T> {
+        let value = self.required(name)?;
+        match value.parse() {
+            Ok(v) => Some(v),
+            Err(_) => {
+                self.error(name, &format!("not {}", what));
+                None
+            }
+        }
+    }
+    fn day(&mut self, name: &str) -> Option<Day> {
+        let value = self.required(name)?;
+        let day = Day::from_str(value);
+        if day.is_none() {
+            self.error(name, "not a capitalized weekday name");
+        }
+        day
+    }
+    fn time(&mut self, name: &str) -> Option<Time> {
+        let value = self.required(name)?;
+        match Time::parse(value) {
+            Some(t) => {
+                let minutes = value
+                    .rsplit(':')
+                    .next()
+                    .map(|m| m.trim_end_matches(|c: char| c.is_ascii_alphabetic()))
+                    .and_then(|m| m.parse::<usize>().ok());
+                if minutes.is_some_and(|m| m % 15 != 0) {
+                    self.warn(name, &format!("rounded down to {}", t.to_string_24h()));
+                }
+                Some(t)
+            }
+            None => {
+                self.error(name, "not a time between 0:00 and 23:45");
+                None
+            }
+        }
+    }
+    fn date(&mut self, name: &str) -> Option<NaiveDate> {
+        let value = self.required(name)?;
+        match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            Ok(d) => Some(d),
+            Err(_) => {
+                self.error(name, "not a YYYY-MM-DD date");
+                None
+            }
+        }
+    }
+    fn window(&mut self, start: &str, end: &str) -> Option<Window> {
+        let (s, e) = (self.time(start), self.time(end));
+        let (s, e) = (s?, e?);
+        if s.get_qi() >= e.get_qi() {
+            self.error(end, "ends before it starts");
+            return None;
+        }
+        Some(Window::new(s, e))
+    }
+    fn finish(self, report: &mut Report) -> bool {
+        //! Move this record's diagnostics into the report, returning whether it is free of errors.
+        let ok = self.diags.iter().all(|d| d.severity != Severity::Error);
+        report.diagnostics.extend(self.diags);
+        ok
+    }
+}
+
+fn read_rows<R, T, F>(
+    file: &str,
+    reader: R,
+    required: &[&str],
+    report: &mut Report,
+    mut build: F,
+) -> Vec<T>
+where
+    R: io::Read,
+    F: FnMut(&mut Row) -> Option<T>,
+{
+    //! Read every record from a CSV file that is free of errors, reporting what's wrong with the rest.
+    let mut rdr = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
     let mut out = Vec::new();
+    let cols = match rdr.headers() {
+        Ok(headers) => match Columns::new(file, headers, required, report) {
+            Some(cols) => cols,
+            None => return out,
+        },
+        Err(e) => {
+            report.push(Diagnostic {
+                severity: Severity::Error,
+                file: file.to_owned(),
+                line: Some(1),
+                column: None,
+                value: String::new(),
+                reason: e.to_string(),
+            });
+            return out;
+        }
+    };
     for result in rdr.records() {
-        let record = result?;
-        if let Ok(event) = build_event(&record) {
-            println!("{:#?}", event);
-            out.push(event);
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                report.push(Diagnostic {
+                    severity: Severity::Error,
+                    file: file.to_owned(),
+                    line: e.position().map(|p| p.line()),
+                    column: None,
+                    value: String::new(),
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let mut row = Row::new(&cols, &record);
+        let built = build(&mut row);
+        if row.finish(report) {
+            out.extend(built);
+        }
+    }
+    out
+}
+
+// ==============================================
+
+const ROSTER_COLUMNS: [&str; 5] = ["id", "cant_work_days", "min_hours", "max_hours", "role"];
+const ROSTER_OPTIONAL: [&str; 2] = ["dob", "rate"];
+const EVENT_COLUMNS: [&str; 8] = [
+    "name",
+    "ev_type",
+    "day",
+    "start",
+    "end",
+    "setup",
+    "cleanup",
+    "staff_req",
+];
+const TRAFFIC_COLUMNS: [&str; 3] = ["day", "hour", "transactions"];
+const AVAILABILITY_COLUMNS: [&str; 4] = ["id", "day", "start", "end"];
+const TIME_OFF_COLUMNS: [&str; 3] = ["id", "first", "status"];
+const SHIFT_COLUMNS: [&str; 4] = ["id", "day", "start", "end"];
+const GRID_COLUMNS: [&str; 1] = ["employee"];
+
+pub fn get_roster(report: &mut Report) -> Result<Roster, Box<dyn Error>> {
+    let file = fs::File::open("./docs/roster.csv")?;
+    Ok(read_roster(file, report))
+}
+
+pub fn read_roster<R: io::Read>(reader: R, report: &mut Report) -> Roster {
+    //! Read a roster from CSV with `ROSTER_COLUMNS` in any order. Every other column is read as an ability.
+    let mut ros = Roster::new();
+    for empl in read_rows("roster.csv", reader, &ROSTER_COLUMNS, report, build_empl) {
+        ros.add(empl);
+    }
+    ros
+}
+
+/// An event read from CSV, with the line it was read from.
+pub type EventRow = (Option<u64>, Event);
+
+pub fn get_events(report: &mut Report) -> Result<Vec<EventRow>, Box<dyn Error>> {
+    let file = fs::File::open("./docs/events.csv")?;
+    Ok(read_events(file, report))
+}
+
+pub fn read_events<R: io::Read>(reader: R, report: &mut Report) -> Vec<EventRow> {
+    //! Read events from CSV with `EVENT_COLUMNS`, and optionally `fixed_emps`, in any order. Each comes with the
+    //! line it was read from, for `check_references` to report against.
+    read_rows("events.csv", reader, &EVENT_COLUMNS, report, |row| {
+        Some((row.line(), build_event(row)?))
+    })
+}
+
+pub fn get_traffic(report: &mut Report) -> Result<Vec<Traffic>, Box<dyn Error>> {
+    let file = fs::File::open("./docs/traffic.csv")?;
+    Ok(read_traffic(file, report))
+}
+
+pub fn read_traffic<R: io::Read>(reader: R, report: &mut Report) -> Vec<Traffic> {
+    read_rows(
+        "traffic.csv",
+        reader,
+        &TRAFFIC_COLUMNS,
+        report,
+        build_traffic,
+    )
+}
+
+pub fn get_availability(ros: &mut Roster, report: &mut Report) -> Result<(), Box<dyn Error>> {
+    let file = fs::File::open("./docs/availability.csv")?;
+    read_availability(file, ros, report);
+    Ok(())
+}
+
+pub fn read_availability<R: io::Read>(reader: R, ros: &mut Roster, report: &mut Report) {
+    //! Read time-window availability into an existing roster.
+    //!
+    //! The first row for an employee and day replaces that day's availability; later rows add further windows.
+    let rows = read_rows(
+        "availability.csv",
+        reader,
+        &AVAILABILITY_COLUMNS,
+        report,
+        |row| {
+            let id = known_employee(row, ros, "id");
+            let (day, window) = (row.day("day"), row.window("start", "end"));
+            Some((id?, day?, window?))
+        },
+    );
+    let mut seen: HashSet<(String, usize)> = HashSet::new();
+    for (id, day, window) in rows {
+        let empl = ros.get_mut(&id).unwrap();
+        if seen.insert((id, day.to_index())) {
+            empl.set_windows(day, vec![window]);
         } else {
-            println!("Error reading record: {:#?}", record);
+            empl.add_window(day, window);
         }
     }
-    Ok(out)
 }
 
-fn build_empl(sr: &csv::StringRecord, headers: &Vec<String>) -> Result<Employee, Box<Error>> {
-    // id
-    let mut empl = Employee::new(sr[0].to_owned());
-    // cant_work_days
-    for word in sr[1].split(", ") {
-        if let Some(d) = Day::from_str(word) {
-            empl.set_available(d, false);
+pub fn get_time_off(ros: &mut Roster, report: &mut Report) -> Result<(), Box<dyn Error>> {
+    let file = fs::File::open("./docs/time_off.csv")?;
+    read_time_off(file, ros, report);
+    Ok(())
+}
+
+pub fn read_time_off<R: io::Read>(reader: R, ros: &mut Roster, report: &mut Report) {
+    //! Read dated time-off requests into an existing roster.
+    let rows = read_rows("time_off.csv", reader, &TIME_OFF_COLUMNS, report, |row| {
+        let id = known_employee(row, ros, "id");
+        let req = build_time_off(row);
+        Some((id?, req?))
+    });
+    for (id, req) in rows {
+        ros.get_mut(&id).unwrap().request_time_off(req);
+    }
+}
+
+pub fn get_shifts(
+    sched: &mut Schedule,
+    ros: &Roster,
+    report: &mut Report,
+) -> Result<usize, Box<dyn Error>> {
+    let file = fs::File::open("./docs/shifts.csv")?;
+    read_shifts(file, sched, ros, report)
+}
+
+pub fn read_shifts<R: io::Read>(
+    mut reader: R,
+    sched: &mut Schedule,
+    ros: &Roster,
+    report: &mut Report,
+) -> Result<usize, Box<dyn Error>> {
+    //! Replace a schedule's shifts with those read from CSV, returning how many were read.
+    //!
+    //! The file may list one shift per row, with `SHIFT_COLUMNS` and optionally `event` and `position`, or be a
+    //! weekly grid as written by `Schedule::write_grid_csv`, with an `employee` column and a column per day.
+    let mut data = String::new();
+    reader.read_to_string(&mut data)?;
+    let first = data.lines().next().unwrap_or("").to_lowercase();
+    let is_grid = first.split(',').any(|h| h.trim() == "employee");
+    let shifts: Vec<(Day, Shift)> = if is_grid {
+        read_rows(
+            "shifts.csv",
+            data.as_bytes(),
+            &GRID_COLUMNS,
+            report,
+            |row| build_grid_row(row, sched, ros),
+        )
+        .into_iter()
+        .flatten()
+        .collect()
+    } else {
+        read_rows(
+            "shifts.csv",
+            data.as_bytes(),
+            &SHIFT_COLUMNS,
+            report,
+            |row| build_shift(row, sched, ros),
+        )
+    };
+    for day_shifts in sched.shifts.iter_mut() {
+        day_shifts.clear();
+    }
+    let count = shifts.len();
+    for (day, sh) in shifts {
+        sched.shifts[day.to_index()].push(sh);
+    }
+    Ok(count)
+}
+
+pub fn check_references(ros: &Roster, events: &[EventRow], report: &mut Report) {
+    //! Check imported events against the roster, reporting every fixed employee who isn't on it, can't run the
+    //! event or isn't available for it, and every event that nobody on the roster is able to run.
+    //!
+    //! Dated time off is left to schedule validation, since it depends on the week being scheduled.
+    let mut error = |(line, event): &EventRow, column: &str, value: &str, reason: String| {
+        report.push(Diagnostic {
+            severity: Severity::Error,
+            file: "events.csv".to_owned(),
+            line: *line,
+            column: Some(column.to_owned()),
+            value: value.to_owned(),
+            reason: format!("{} ({} on {})", reason, event.name, event.day),
+        });
+    };
+    for located in events {
+        let event = &located.1;
+        if !ros.iter().any(|(_, em)| em.is_able(&event.kind)) {
+            error(
+                located,
+                "ev_type",
+                &event.kind,
+                "nobody on the roster can run this".to_owned(),
+            );
         }
+        let start = event.start.get_qi().checked_sub(event.setup.get_qi());
+        let end = event.end.get_qi() + event.breakdown.get_qi();
+        let span = match start {
+            Some(start) if end < 24 * 4 => Some((Time::from_qi(start), Time::from_qi(end))),
+            _ => {
+                error(
+                    located,
+                    "setup",
+                    &event.setup.to_string_24h(),
+                    "setup or cleanup runs past midnight".to_owned(),
+                );
+                None
+            }
+        };
+        for id in event.req_ids() {
+            let em = match ros.get(id) {
+                Some(em) => em,
+                None => {
+                    error(
+                        located,
+                        "fixed_emps",
+                        id,
+                        "no such employee on the roster".to_owned(),
+                    );
+                    continue;
+                }
+            };
+            if !em.is_able(&event.kind) {
+                error(
+                    located,
+                    "fixed_emps",
+                    id,
+                    format!("can't run {}", event.kind),
+                );
+            }
+            if let Some((start, end)) = &span {
+                if !em.is_available_for(&event.day, start, end) {
+                    error(
+                        located,
+                        "fixed_emps",
+                        id,
+                        format!("not available {} - {}", start, end),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn known_employee(row: &mut Row, ros: &Roster, column: &str) -> Option<String> {
+    let id = row.required(column)?;
+    if ros.iter().any(|(k, _)| k == id) {
+        Some(id.to_owned())
+    } else {
+        row.error(column, "no such employee on the roster");
+        None
     }
-    // min_ and max_hours
-    let min: usize = sr[2].parse()?;
-    let max: usize = sr[3].parse()?;
-    empl.hours().set(min, max);
+}
 
-    for role in sr[4].split(", ") {
-        empl.add_role(role);
+fn known_event(row: &mut Row, sched: &Schedule, column: &str, day: &Day, name: &str) -> bool {
+    if sched
+        .events
+        .iter()
+        .any(|ev| ev.name == name && ev.day == *day)
+    {
+        true
+    } else {
+        row.error(column, &format!("no event \"{}\" on {}", name, day));
+        false
     }
+}
 
-    for i in 5..sr.len() {
-        if &sr[i] != "" {
-            empl.set_abil(&headers[i], sr[i].parse()?);
+fn build_shift(row: &mut Row, sched: &Schedule, ros: &Roster) -> Option<(Day, Shift)> {
+    let id = known_employee(row, ros, "id");
+    let (day, window) = (row.day("day"), row.window("start", "end"));
+    let (id, day, window) = (id?, day?, window?);
+    let mut sh = Shift::new(id, window.start, window.end);
+    let event = row.text("event");
+    if !event.is_empty() {
+        if !known_event(row, sched, "event", &day, event) {
+            return None;
         }
+        sh.event = Some(event.to_owned());
     }
+    let position = row.text("position");
+    if !position.is_empty() {
+        sh.position = Some(position.to_owned());
+    }
+    Some((day, sh))
+}
 
-    Ok(empl)
+fn build_grid_row(row: &mut Row, sched: &Schedule, ros: &Roster) -> Option<Vec<(Day, Shift)>> {
+    //! Read one employee's row of the weekly grid, whose cells hold shifts such as "10:00a-2:00p; 4:00p-9:00p FNM".
+    let mut out = Vec::new();
+    if row.text("employee").is_empty() || row.text("employee") == "Total" {
+        return Some(out);
+    }
+    let id = known_employee(row, ros, "employee")?;
+    for day in (0..7).map(|i| Day::from_index(i).unwrap()) {
+        let column = day.to_string().to_lowercase();
+        let cell = row.text(&column);
+        for piece in cell.split(';').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            let (times, event) = match piece.split_once(' ') {
+                Some((times, event)) => (times, Some(event.trim())),
+                None => (piece, None),
+            };
+            let span = times
+                .split_once('-')
+                .and_then(|(s, e)| Some((Time::parse(s)?, Time::parse(e)?)));
+            let (start, end) = match span {
+                Some((s, e)) if s.get_qi() < e.get_qi() => (s, e),
+                _ => {
+                    row.error(
+                        &column,
+                        &format!("\"{}\" isn't a start-end time span", times),
+                    );
+                    continue;
+                }
+            };
+            let mut sh = Shift::new(id.clone(), start, end);
+            if let Some(event) = event {
+                if !known_event(row, sched, &column, &day, event) {
+                    continue;
+                }
+                sh.event = Some(event.to_owned());
+            }
+            out.push((day.clone(), sh));
+        }
+    }
+    Some(out)
 }
 
-fn build_event(sr: &csv::StringRecord) -> Result<Event, Box<Error>> {
-    let name = sr[0].to_owned();
-    let kind = sr[1].to_owned();
-    let day = Day::from_str(&sr[2]).ok_or("bad day string")?;
-    let start = Time::from_str(&sr[3]);
-    let end = Time::from_str(&sr[4]);
-    let setup = Time::from_str(&sr[5]);
-    let breakdown = Time::from_str(&sr[6]);
-    let num_emps: i32 = sr[7].parse()?;
+fn build_empl(row: &mut Row) -> Option<Employee> {
+    let id = row.required("id");
+    let min: Option<usize> = row.parse("min_hours", "a whole number of hours");
+    let max: Option<usize> = row.parse("max_hours", "a whole number of hours");
+    let mut empl = Employee::new(id?.to_owned());
+    for word in row.text("cant_work_days").split(',') {
+        let word = word.trim();
+        if word.is_empty() {
+            continue;
+        }
+        match Day::from_str(word) {
+            Some(d) => {
+                empl.set_available(d, false);
+            }
+            None => row.warn(
+                "cant_work_days",
+                &format!("ignored \"{}\", not a capitalized weekday name", word),
+            ),
+        }
+    }
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            row.error("min_hours", "more than max_hours");
+        }
+        empl.hours().set(min, max);
+    }
+
+    for role in row.text("role").split(',') {
+        if !role.trim().is_empty() {
+            empl.add_role(role.trim());
+        }
+    }
+
+    if !row.text("dob").is_empty() {
+        empl.set_dob(row.date("dob"));
+    }
+    if !row.text("rate").is_empty() {
+        empl.set_rate(row.parse("rate", "an hourly rate"));
+    }
+
+    // Any column we don't otherwise know is an ability, named by its header.
+    let cols = row.cols;
+    for (name, header) in cols.names.iter().zip(cols.headers.iter()) {
+        if ROSTER_COLUMNS.contains(&name.as_str()) || ROSTER_OPTIONAL.contains(&name.as_str()) {
+            continue;
+        }
+        if !row.text(name).is_empty() {
+            if let Some(v) = row.parse(name, "an ability from 0 to 255") {
+                empl.set_abil(header, v);
+            }
+        }
+    }
+
+    Some(empl)
+}
+
+fn build_event(row: &mut Row) -> Option<Event> {
+    let name = row.required("name").map(|s| s.to_owned());
+    let kind = row.required("ev_type").map(|s| s.to_owned());
+    let day = row.day("day");
+    let start = row.time("start");
+    let end = row.time("end");
+    let setup = row.time("setup");
+    let breakdown = row.time("cleanup");
+    let num_emps: Option<i32> = row.parse("staff_req", "a whole number of staff");
     let mut req_emp_ids: Vec<String> = Vec::new();
-    if &sr[8] != "" {
-        for empl in sr[8].split(", ") {
-            req_emp_ids.push(empl.to_owned());
+    for empl in row.text("fixed_emps").split(',') {
+        if !empl.trim().is_empty() {
+            req_emp_ids.push(empl.trim().to_owned());
+        }
+    }
+    if let (Some(s), Some(e)) = (&start, &end) {
+        if s.get_qi() >= e.get_qi() {
+            row.error("end", "ends before it starts");
+        }
+    }
+    // Setup and cleanup have to fit within the day the event is on.
+    if let (Some(s), Some(setup)) = (&start, &setup) {
+        if s.get_qi() < setup.get_qi() {
+            row.error("setup", "setup would start before midnight");
+        }
+    }
+    if let (Some(e), Some(cleanup)) = (&end, &breakdown) {
+        if e.get_qi() + cleanup.get_qi() >= 24 * 4 {
+            row.error("cleanup", "cleanup would run past 23:45");
         }
     }
     let out = Event {
-        name,
+        name: name?,
         req_emp_ids,
-        day,
-        start,
-        end,
-        num_emps,
-        kind,
-        setup,
-        breakdown,
+        day: day?,
+        start: start?,
+        end: end?,
+        num_emps: num_emps?,
+        kind: kind?,
+        setup: setup?,
+        breakdown: breakdown?,
     };
-    Ok(out)
+    Some(out)
+}
+
+fn build_traffic(row: &mut Row) -> Option<Traffic> {
+    let day = row.day("day");
+    let hour = row.time("hour");
+    let transactions: Option<f64> = row.parse("transactions", "a number");
+    Some(Traffic {
+        day: day?,
+        hour: hour?,
+        transactions: transactions?,
+    })
+}
+
+fn build_time_off(row: &mut Row) -> Option<TimeOff> {
+    let first = row.date("first");
+    let last = if row.text("last").is_empty() {
+        first
+    } else {
+        row.date("last")
+    };
+    let window = if row.text("start").is_empty() && row.text("end").is_empty() {
+        None
+    } else {
+        Some(row.window("start", "end"))
+    };
+    let status = Status::from_str(row.text("status"));
+    if status.is_none() {
+        row.error("status", "not Pending, Approved or Denied");
+    }
+    let (first, last) = (first?, last?);
+    if last < first {
+        row.error("last", "before the first day off");
+    }
+    let mut req = TimeOff::new(first, last);
+    if let Some(window) = window {
+        req.window = Some(window?);
+    }
+    match status? {
+        Status::Approved => req.approve(),
+        Status::Denied => req.deny(),
+        Status::Pending => (),
+    }
+    req.reason = row.text("reason").to_owned();
+    Some(req)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::valid::Problem;
+    #[test]
+    fn roster_any_order() {
+        let data = "\
+role,Magic,max_hours,id,notes,min_hours,cant_work_days,dob
+Manager,5,40,Matt,,30,\"Sunday, Monday\",1990-04-01
+Associate,,20,Jo,,10,,
+";
+        let mut report = Report::new();
+        let ros = read_roster(data.as_bytes(), &mut report);
+        assert!(report.is_empty());
+        let matt = ros.get("Matt").unwrap();
+        assert_eq!(matt.get_hours().max(), 40);
+        assert!(matt.has_role("Manager"));
+        assert_eq!(matt.get_abil("Magic"), Some(5));
+        assert!(!matt.is_available(Day::Monday));
+        assert!(matt.dob().is_some());
+        let jo = ros.get("Jo").unwrap();
+        assert_eq!(jo.get_abil("Magic"), None);
+    }
+    #[test]
+    fn references() {
+        let mut report = Report::new();
+        let data = "\
+id,cant_work_days,min_hours,max_hours,role,Magic,Yugioh
+Matt,Friday,30,40,Manager,5,
+Jo,,10,20,Associate,,2
+";
+        let ros = read_roster(data.as_bytes(), &mut report);
+        let data = "\
+name,ev_type,day,start,end,setup,cleanup,staff_req,fixed_emps
+FNM,Magic,Friday,18:00,22:00,0:30,0:30,2,\"Matt, Jo, Ghost\"
+Locals,Yugioh,Friday,18:00,22:00,0:30,0:30,1,Jo
+Pokemon League,Pokemon,Saturday,12:00,16:00,0:30,0:30,1,
+";
+        let evs = read_events(data.as_bytes(), &mut report);
+        assert!(report.is_empty());
+        check_references(&ros, &evs, &mut report);
+        let found: Vec<_> = report
+            .errors()
+            .map(|d| (d.line, d.column.clone().unwrap(), d.value.clone()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some(2), "fixed_emps".to_string(), "Matt".to_string()),
+                (Some(2), "fixed_emps".to_string(), "Jo".to_string()),
+                (Some(2), "fixed_emps".to_string(), "Ghost".to_string()),
+                (Some(4), "ev_type".to_string(), "Pokemon".to_string()),
+            ]
+        );
+    }
+    #[test]
+    fn missing_columns() {
+        let data = "id,min_hours\nMatt,30\n";
+        let mut report = Report::new();
+        read_roster(data.as_bytes(), &mut report);
+        let missing: Vec<_> = report.errors().map(|d| d.column.clone().unwrap()).collect();
+        assert_eq!(missing, vec!["cant_work_days", "max_hours", "role"]);
+        let data = "ev_type,name,day,start,end,setup,cleanup,staff_req,extra\n\
+                    Magic,FNM,Friday,18:00,22:00,0:30,0:30,2,x\n";
+        let evs = read_events(data.as_bytes(), &mut report);
+        assert_eq!(evs[0].1.name, "FNM");
+        assert!(evs[0].1.req_emp_ids.is_empty());
+    }
+    #[test]
+    fn diagnostics() {
+        let data = "\
+name,ev_type,day,start,end,setup,cleanup,staff_req
+FNM,Magic,Friday,18:00,25:00,0:30,0:30,2
+Draft,Magic,Funday,18:10,22:00,0:30,0:30,two
+Prerelease,Magic,Saturday,10:00,18:00,1:00,1:00,4
+Late Night,Magic,Friday,21:00,23:00,0:30,2:00,1
+Midnight Release,Magic,Saturday,0:30,2:00,1:00,0:30,1
+";
+        let mut report = Report::new();
+        let evs = read_events(data.as_bytes(), &mut report);
+        assert_eq!(evs.len(), 1);
+        assert_eq!(report.errors().count(), 5);
+        let late: Vec<_> = report
+            .errors()
+            .skip(3)
+            .map(|d| (d.line, d.column.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            late,
+            vec![
+                (Some(5), "cleanup".to_string()),
+                (Some(6), "setup".to_string())
+            ]
+        );
+        assert_eq!(report.warnings().count(), 1);
+        let bad = report.errors().next().unwrap();
+        assert_eq!(bad.line, Some(2));
+        assert_eq!(bad.column, Some("end".to_string()));
+        assert_eq!(
+            bad.to_string(),
+            "error: events.csv:2 [end] \"25:00\": not a time between 0:00 and 23:45"
+        );
+    }
+    #[test]
+    fn shifts_round_trip() {
+        let mut ros = Roster::new();
+        for id in ["Kim", "Lou"].iter() {
+            ros.add(Employee::new(id.to_string()));
+        }
+        let mut sched = Schedule::new();
+        sched.add_event(
+            "FNM",
+            "Magic",
+            Day::Friday,
+            Time::from_hour(17),
+            Time::from_hour(21),
+        );
+        let ev = sched.events[0].clone();
+        sched.assign_event("Lou".to_string(), ev);
+        sched.assign_shift(
+            "Kim".to_string(),
+            Day::Saturday,
+            Time::from_str("9:45"),
+            Time::from_hour(14),
+        );
+        sched.assign_shift(
+            "Kim".to_string(),
+            Day::Saturday,
+            Time::from_hour(16),
+            Time::from_str("20:30"),
+        );
+        let mut grid = Vec::new();
+        sched.grid(&ros).write_csv(&mut grid).unwrap();
+        let mut copy = sched.clone();
+        let mut report = Report::new();
+        let read = read_shifts(grid.as_slice(), &mut copy, &ros, &mut report).unwrap();
+        assert!(report.is_empty());
+        assert_eq!(read, 3);
+        assert_eq!(copy, sched);
+    }
+    #[test]
+    fn shift_list() {
+        let mut ros = Roster::new();
+        ros.add(Employee::new("Kim".to_string()));
+        let mut sched = Schedule::new();
+        let data = "\
+id,day,start,end,event
+Kim,Saturday,10:00,14:00,
+Kim,Saturday,13:00,17:00,
+Kim,Sunday,10:00,14:00,League
+Zed,Sunday,10:00,14:00,
+";
+        let mut report = Report::new();
+        let read = read_shifts(data.as_bytes(), &mut sched, &ros, &mut report).unwrap();
+        assert_eq!(read, 2);
+        assert_eq!(report.errors().count(), 2);
+        // The overlapping shifts are read as given, for validation to catch.
+        assert!(sched
+            .problems(&ros)
+            .iter()
+            .any(|p| matches!(p, Problem::DoubleBooked { .. })));
+    }
 }
@@ -0,0 +1,166 @@
+//! The keys module makes sure someone with the store keys opens and closes each day.
+use super::emp::Roster;
+use super::time::{Day, Schedule};
+use super::valid::Problem;
+use std::fmt;
+
+// ==============================================
+
+/// Who opens and who closes the store on one day, if anyone with the keys does.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyHolders {
+    pub day: Day,
+    pub opener: Option<String>,
+    pub closer: Option<String>,
+}
+
+impl fmt::Display for KeyHolders {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let nobody = "nobody".to_string();
+        write!(
+            f,
+            "{}: opened by {}, closed by {}",
+            self.day,
+            self.opener.as_ref().unwrap_or(&nobody),
+            self.closer.as_ref().unwrap_or(&nobody)
+        )
+    }
+}
+
+// ==============================================
+
+impl Schedule {
+    fn holds_keys(&self, id: &str, ros: &Roster) -> bool {
+        match self.key_role() {
+            Some(role) => ros.get(id).is_some_and(|em| em.has_role(role)),
+            None => false,
+        }
+    }
+    // Access
+    pub fn key_holders(&self, day: &Day, ros: &Roster) -> Option<KeyHolders> {
+        //! Return the key holders working from the start of the day's pre-open window and through the end of its
+        //! post-close window, or None if the store is shut that day.
+        let (open, close) = (self.opening(day)?, self.closing(day)?);
+        let mut opener = None;
+        let mut closer = None;
+        let mut shifts: Vec<_> = self.shifts(day).iter().collect();
+        shifts.sort_by_key(|sh| (sh.start.get_qi(), sh.emp_id.clone()));
+        for sh in shifts {
+            if !self.holds_keys(&sh.emp_id, ros) {
+                continue;
+            }
+            if opener.is_none() && sh.start.get_qi() <= open && open < sh.end.get_qi() {
+                opener = Some(sh.emp_id.clone());
+            }
+            if closer.is_none() && sh.start.get_qi() < close && close <= sh.end.get_qi() {
+                closer = Some(sh.emp_id.clone());
+            }
+        }
+        Some(KeyHolders {
+            day: day.clone(),
+            opener,
+            closer,
+        })
+    }
+    pub fn key_report(&self, ros: &Roster) -> Vec<KeyHolders> {
+        //! List the opening and closing key holders for every day the store is open.
+        (0..7)
+            .filter_map(|i| self.key_holders(&Day::from_index(i).unwrap(), ros))
+            .collect()
+    }
+    // Validation
+    pub fn key_problems(&self, ros: &Roster) -> Vec<Problem> {
+        //! List every opening or closing without a key holder, if a key role is set.
+        let mut out = Vec::new();
+        if self.key_role().is_none() {
+            return out;
+        }
+        for keys in self.key_report(ros) {
+            if keys.opener.is_none() {
+                out.push(Problem::NoKeys {
+                    day: keys.day.clone(),
+                    opening: true,
+                });
+            }
+            if keys.closer.is_none() {
+                out.push(Problem::NoKeys {
+                    day: keys.day,
+                    opening: false,
+                });
+            }
+        }
+        out
+    }
+    // Generation
+    pub(crate) fn key_value(
+        &self,
+        day: &Day,
+        id: &str,
+        start: usize,
+        end: usize,
+        ros: &Roster,
+    ) -> usize {
+        //! Return how many of the day's missing openings and closings a key holder's shift would cover.
+        if !self.holds_keys(id, ros) {
+            return 0;
+        }
+        let keys = match self.key_holders(day, ros) {
+            Some(keys) => keys,
+            None => return 0,
+        };
+        let mut out = 0;
+        if keys.opener.is_none() && self.opening(day).is_some_and(|o| start <= o && o < end) {
+            out += 1;
+        }
+        if keys.closer.is_none() && self.closing(day).is_some_and(|c| start < c && c <= end) {
+            out += 1;
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::gen::Objective;
+    use crate::time::Time;
+    fn setup() -> (Schedule, Roster) {
+        let (mut sched, ros) = Fixture::new()
+            .employee("Kim", |em| em.add_role("Keyholder"))
+            .staff(&["Lou"])
+            .build();
+        sched.set_key_role(Some("Keyholder"));
+        sched.set_hours(Day::Saturday, 10, 16);
+        (sched, ros)
+    }
+    #[test]
+    fn open_and_close() {
+        let (mut sched, ros) = setup();
+        assert_eq!(sched.key_problems(&ros).len(), 2);
+        sched.assign_shift(
+            "Kim".to_string(),
+            Day::Saturday,
+            Time::from_str("09:45"),
+            Time::from_hour(14),
+        );
+        sched.assign_shift(
+            "Lou".to_string(),
+            Day::Saturday,
+            Time::from_hour(12),
+            Time::from_str("17:00"),
+        );
+        let keys = sched.key_holders(&Day::Saturday, &ros).unwrap();
+        assert_eq!(keys.opener, Some("Kim".to_string()));
+        assert_eq!(keys.closer, None);
+        assert_eq!(sched.key_problems(&ros).len(), 1);
+    }
+    #[test]
+    fn generation_sends_keys() {
+        let (mut sched, ros) = setup();
+        sched.fill_shifts(&ros, &Objective::default());
+        let keys = sched.key_holders(&Day::Saturday, &ros).unwrap();
+        assert_eq!(keys.opener, Some("Kim".to_string()));
+        assert_eq!(keys.closer, Some("Kim".to_string()));
+    }
+}
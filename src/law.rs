@@ -0,0 +1,334 @@
+//! The law module holds named packs of labor-law rules that schedules can be held to.
+use super::emp::Employee;
+use super::time::{Day, Schedule, Shift, Time, Window};
+use super::valid::Problem;
+use chrono::NaiveDate;
+#[cfg(feature = "project")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// ==============================================
+
+/// A single labor-law rule.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub enum Rule {
+    /// Employees younger than `age` may not work during `window` on any of `days`.
+    SchoolHours {
+        age: u32,
+        days: Vec<Day>,
+        window: Window,
+    },
+    /// Employees younger than `age` may not work past `latest` on any of `days`.
+    SchoolNights {
+        age: u32,
+        days: Vec<Day>,
+        latest: Time,
+    },
+    /// Time worked past `after` hours in one day is paid at `multiplier` times the usual rate.
+    DailyOvertime { after: usize, multiplier: f64 },
+    /// A week's schedule must be published at least `days` days before the week begins.
+    Notice { days: i64 },
+    /// Shifts longer than `after` hours include an unpaid break of `minutes`.
+    MealBreak { after: usize, minutes: usize },
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule::SchoolHours { age, window, .. } => {
+                write!(f, "under {} can't work {} on school days", age, window)
+            }
+            Rule::SchoolNights { age, latest, .. } => {
+                write!(
+                    f,
+                    "under {} can't work past {} on school nights",
+                    age, latest
+                )
+            }
+            Rule::DailyOvertime { after, multiplier } => {
+                write!(f, "{}x pay past {} hours a day", multiplier, after)
+            }
+            Rule::Notice { days } => write!(f, "{} days' notice of schedules", days),
+            Rule::MealBreak { after, minutes } => {
+                write!(
+                    f,
+                    "{} minute unpaid break in shifts over {} hours",
+                    minutes, after
+                )
+            }
+        }
+    }
+}
+
+// ==============================================
+
+/// A named set of rules for a jurisdiction, selected with `Schedule::add_rule_pack`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub struct RulePack {
+    pub name: String,
+    pub rules: Vec<Rule>,
+}
+
+impl RulePack {
+    // Constructors
+    pub fn new(name: &str) -> RulePack {
+        //! Create a new, empty rule pack.
+        RulePack {
+            name: name.to_owned(),
+            rules: Vec::new(),
+        }
+    }
+    pub fn builtin(name: &str) -> Option<RulePack> {
+        //! Return one of the built-in rule packs by name (see `RulePack::builtin_names`).
+        //!
+        //! # Examples
+        //! ```
+        //! use makeshift::law::RulePack;
+        //! let pack = RulePack::builtin("daily-overtime").unwrap();
+        //! assert_eq!(pack.rules.len(), 1);
+        //! assert!(RulePack::builtin("atlantis").is_none());
+        //! ```
+        let school_days = vec![
+            Day::Monday,
+            Day::Tuesday,
+            Day::Wednesday,
+            Day::Thursday,
+            Day::Friday,
+        ];
+        let school_nights = vec![
+            Day::Sunday,
+            Day::Monday,
+            Day::Tuesday,
+            Day::Wednesday,
+            Day::Thursday,
+        ];
+        let mut pack = RulePack::new(name);
+        match name {
+            "minors" => {
+                pack.add(Rule::SchoolHours {
+                    age: 18,
+                    days: school_days,
+                    window: Window::new(Time::from_hour(8), Time::from_hour(15)),
+                });
+                pack.add(Rule::SchoolNights {
+                    age: 18,
+                    days: school_nights,
+                    latest: Time::from_hour(19),
+                });
+            }
+            "daily-overtime" => pack.add(Rule::DailyOvertime {
+                after: 8,
+                multiplier: 1.5,
+            }),
+            "predictive-scheduling" => pack.add(Rule::Notice { days: 14 }),
+            "meal-breaks" => pack.add(Rule::MealBreak {
+                after: 6,
+                minutes: 30,
+            }),
+            _ => return None,
+        }
+        Some(pack)
+    }
+    pub fn builtin_names() -> Vec<&'static str> {
+        vec![
+            "minors",
+            "daily-overtime",
+            "predictive-scheduling",
+            "meal-breaks",
+        ]
+    }
+    // Modification
+    pub fn add(&mut self, rule: Rule) {
+        self.rules.push(rule);
+    }
+}
+
+impl fmt::Display for RulePack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = format!("=== {} ===", self.name);
+        for rule in self.rules.iter() {
+            out.push_str(&format!("\n{}", rule));
+        }
+        write!(f, "{}", out)
+    }
+}
+
+// ==============================================
+
+impl Schedule {
+    fn rules(&self) -> Vec<(&str, &Rule)> {
+        let mut out = Vec::new();
+        for pack in self.rule_packs() {
+            for rule in pack.rules.iter() {
+                out.push((pack.name.as_str(), rule));
+            }
+        }
+        out
+    }
+    pub fn daily_overtime(&self) -> Option<(usize, f64)> {
+        //! Return the strictest daily overtime threshold, in hours, and its multiplier from the selected rule packs.
+        self.rules()
+            .iter()
+            .filter_map(|(_, rule)| match rule {
+                Rule::DailyOvertime { after, multiplier } => Some((*after, *multiplier)),
+                _ => None,
+            })
+            .min_by_key(|(after, _)| *after)
+    }
+    pub fn break_quarters(&self, sh: &Shift) -> usize {
+        //! Return how many quarter-hours of a shift are an unpaid break, by the longest break any selected rule
+        //! pack requires of it.
+        self.rules()
+            .iter()
+            .filter_map(|(_, rule)| match rule {
+                Rule::MealBreak { after, minutes } if sh.len() > after * 4 => {
+                    Some(minutes.div_ceil(15).min(sh.len()))
+                }
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+    pub fn law_problems(&self, em: &Employee) -> Vec<Problem> {
+        //! List every selected labor-law rule this schedule breaks for one employee.
+        //!
+        //! The rules depend on the employee's age on the day, so they are only checked once the schedule is
+        //! anchored to a week (see `Schedule::set_week`).
+        let mut out = Vec::new();
+        for (day, sh) in self.shifts_of(&em.id()) {
+            let date = match self.date_of(&day) {
+                Some(date) => date,
+                None => continue,
+            };
+            let age = match em.age_on(date) {
+                Some(age) => age,
+                None => continue,
+            };
+            for (pack, rule) in self.rules() {
+                let broken = match rule {
+                    Rule::SchoolHours {
+                        age: under,
+                        days,
+                        window,
+                    } => age < *under && days.contains(&day) && window.overlaps(&sh.start, &sh.end),
+                    Rule::SchoolNights {
+                        age: under,
+                        days,
+                        latest,
+                    } => age < *under && days.contains(&day) && sh.end.get_qi() > latest.get_qi(),
+                    _ => false,
+                };
+                if broken {
+                    out.push(Problem::Law {
+                        emp_id: em.id(),
+                        day: day.clone(),
+                        pack: pack.to_owned(),
+                        rule: rule.to_string(),
+                    });
+                }
+            }
+        }
+        out
+    }
+    pub fn notice_problems(&self, today: NaiveDate) -> Vec<Problem> {
+        //! List any notice rule this schedule breaks, counting from when it was published or else from `today`.
+        let week = match self.week() {
+            Some(week) => week,
+            None => return Vec::new(),
+        };
+        let given = (week - self.published().unwrap_or(today)).num_days();
+        let mut out = Vec::new();
+        for (pack, rule) in self.rules() {
+            if let Rule::Notice { days } = rule {
+                if given < *days {
+                    out.push(Problem::Notice {
+                        pack: pack.to_owned(),
+                        given,
+                        required: *days,
+                    });
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emp::Roster;
+    use crate::fixture::Fixture;
+    fn setup() -> (Schedule, Roster) {
+        let (mut sched, ros) = Fixture::new()
+            .employee("Kid", |em| em.set_dob(NaiveDate::from_ymd_opt(2003, 1, 1)))
+            .dated()
+            .build();
+        sched.add_rule_pack(RulePack::builtin("minors").unwrap());
+        (sched, ros)
+    }
+    #[test]
+    fn minors() {
+        let (mut sched, ros) = setup();
+        sched.assign_shift(
+            "Kid".to_string(),
+            Day::Saturday,
+            Time::from_hour(9),
+            Time::from_hour(21),
+        );
+        sched.assign_shift(
+            "Kid".to_string(),
+            Day::Tuesday,
+            Time::from_hour(15),
+            Time::from_hour(19),
+        );
+        assert!(sched.law_problems(ros.get("Kid").unwrap()).is_empty());
+        sched.assign_shift(
+            "Kid".to_string(),
+            Day::Wednesday,
+            Time::from_hour(16),
+            Time::from_hour(21),
+        );
+        sched.assign_shift(
+            "Kid".to_string(),
+            Day::Friday,
+            Time::from_hour(14),
+            Time::from_hour(18),
+        );
+        assert_eq!(sched.law_problems(ros.get("Kid").unwrap()).len(), 2);
+        // Without dates there is no telling how old anyone is.
+        let mut loose = Schedule::new();
+        loose.add_rule_pack(RulePack::builtin("minors").unwrap());
+        loose.shifts = sched.shifts.clone();
+        assert!(loose.law_problems(ros.get("Kid").unwrap()).is_empty());
+    }
+    #[test]
+    fn notice() {
+        let (mut sched, ros) = setup();
+        sched.add_rule_pack(RulePack::builtin("predictive-scheduling").unwrap());
+        let today = NaiveDate::from_ymd_opt(2019, 5, 30).unwrap();
+        assert_eq!(sched.notice_problems(today).len(), 1);
+        // Notice is only given, and so only checked, once the schedule is published.
+        assert!(sched.store_problems(&ros).is_empty());
+        sched.publish(today);
+        assert_eq!(sched.store_problems(&ros).len(), 1);
+        sched.publish(NaiveDate::from_ymd_opt(2019, 5, 20).unwrap());
+        assert!(sched.notice_problems(today).is_empty());
+    }
+    #[test]
+    fn daily_overtime() {
+        let (mut sched, mut ros) = setup();
+        ros.get_mut("Kid").unwrap().set_rate(Some(10.0));
+        sched.add_rule_pack(RulePack::builtin("daily-overtime").unwrap());
+        sched.assign_shift(
+            "Kid".to_string(),
+            Day::Saturday,
+            Time::from_hour(9),
+            Time::from_hour(19),
+        );
+        let cost = sched.cost(&ros);
+        assert_eq!(cost.overtime_hours, 2.0);
+        assert_eq!(cost.total, 110.0);
+    }
+}
@@ -1,3 +1,25 @@
+pub mod cost;
+pub mod diff;
 pub mod emp;
+#[cfg(test)]
+mod fixture;
+pub mod forecast;
+pub mod gantt;
+pub mod gen;
+pub mod grid;
+pub mod html;
+pub mod ics;
 pub mod import;
+pub mod keys;
+pub mod law;
+pub mod org;
+pub mod pair;
+#[cfg(feature = "project")]
+pub mod project;
+pub mod station;
+#[cfg(feature = "sqlite")]
+pub mod store;
 pub mod time;
+pub mod timesheet;
+pub mod train;
+pub mod valid;
@@ -1,22 +1,136 @@
+use chrono::NaiveDate;
 use makeshift::{
-    self, import,
+    self,
+    forecast::{Profile, StaffRatio},
+    gen::Objective,
+    import::{self, Report},
     time::{Day, Schedule},
+    timesheet::TimesheetFormat,
 };
+use std::env;
+use std::path::Path;
+use std::process;
 
 fn main() {
-    let ros = import::get_roster().unwrap();
-    let mut sched = get_schedule();
-    let evs = import::get_events().unwrap();
-    sched.events.extend(evs);
-    sched.assign_required_shifts(&ros);
+    let mut report = Report::new();
+    let mut ros = import::get_roster(&mut report).unwrap();
+    if let Err(e) = import::get_availability(&mut ros, &mut report) {
+        println!("No availability windows loaded: {}", e);
+    }
+    if let Err(e) = import::get_time_off(&mut ros, &mut report) {
+        println!("No time off loaded: {}", e);
+    }
+    let week = match env::args().nth(1) {
+        Some(arg) => match NaiveDate::parse_from_str(&arg, "%Y-%m-%d") {
+            Ok(date) => Some(date),
+            Err(_) => {
+                println!(
+                    "\"{}\" is not a date; give the week to schedule as YYYY-MM-DD.",
+                    arg
+                );
+                process::exit(1);
+            }
+        },
+        None => {
+            println!("No week given, so time off and other dated rules can't be checked.");
+            None
+        }
+    };
+    let mut sched = get_schedule(week);
+    if let Ok(history) = import::get_traffic(&mut report) {
+        let profile = Profile::from_history(&history);
+        profile.apply(&mut sched, &StaffRatio::new(12.0));
+    }
+    let evs = import::get_events(&mut report).unwrap();
+    import::check_references(&ros, &evs, &mut report);
+    sched.events.extend(evs.into_iter().map(|(_, ev)| ev));
+    // A schedule saved or edited by hand is checked rather than regenerated.
+    let imported = Path::new("docs/shifts.csv").exists();
+    if imported {
+        if let Err(e) = import::get_shifts(&mut sched, &ros, &mut report) {
+            println!("Shifts not loaded: {}", e);
+        }
+    }
+    if !report.is_empty() {
+        println!("{}", report);
+    }
+    if report.has_errors() {
+        println!("Fix the errors above and run again.");
+        process::exit(1);
+    }
+    if imported {
+        for problem in sched.problems(&ros) {
+            println!("{}", problem);
+        }
+    } else {
+        sched.assign_required_shifts(&ros);
+        sched.fill_shifts(&ros, &Objective::default());
+        sched.assign_positions(&ros);
+    }
     println!("{}", ros);
     println!("{}", sched);
-    sched.expand_shifts("Matt".to_string());
+    let width = env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .unwrap_or(80);
+    println!("{}", sched.gantt_week(width));
+    for tally in sched.preference_report(&ros) {
+        println!("{}", tally);
+    }
+    for keys in sched.key_report(&ros) {
+        println!("{}", keys);
+    }
+    println!("{}", sched.cost(&ros));
+    if let Err(e) = sched.write_grid_csv(Path::new("schedule.csv"), &ros) {
+        println!("Schedule grid not written: {}", e);
+    }
+    if let Err(e) = sched.write_grid_xlsx(Path::new("schedule.xlsx"), &ros) {
+        println!("Schedule workbook not written: {}", e);
+    }
+    if let Err(e) = sched.write_html(Path::new("schedule.html")) {
+        println!("Schedule report not written: {}", e);
+    }
+    let columns = Path::new("docs/timesheet_columns.csv");
+    let format = if columns.exists() {
+        TimesheetFormat::load(columns).unwrap_or_else(|e| {
+            println!("Timesheet columns not loaded, using defaults: {}", e);
+            TimesheetFormat::default()
+        })
+    } else {
+        TimesheetFormat::default()
+    };
+    if let Err(e) = sched.write_timesheet(Path::new("timesheet.csv"), &ros, &format) {
+        println!("Timesheet not written: {}", e);
+    }
+    if let Err(e) = sched.write_calendars(Path::new("calendars"), &ros) {
+        println!("Calendars not written: {}", e);
+    }
+    #[cfg(feature = "sqlite")]
+    if let Err(e) = keep(&ros, &sched) {
+        println!("Database not updated: {}", e);
+    }
+    sched.expand_shifts("Matt".to_string(), &ros);
 }
 
-/// Return the full week's schedule for Labyrinth.
-fn get_schedule() -> Schedule {
+/// Save the roster and store setup to ./makeshift.db, along with the schedule once it has been published.
+#[cfg(feature = "sqlite")]
+fn keep(ros: &makeshift::emp::Roster, sched: &Schedule) -> Result<(), Box<dyn std::error::Error>> {
+    let mut db = makeshift::store::Store::open(Path::new("makeshift.db"))?;
+    db.save_roster(ros)?;
+    db.save_setup(sched)?;
+    if sched.published().is_some() {
+        db.publish(sched)?;
+    }
+    Ok(())
+}
+
+/// Return the full week's schedule for Labyrinth, anchored to the week containing `week` if one is given.
+fn get_schedule(week: Option<NaiveDate>) -> Schedule {
     let mut sched = Schedule::new();
+    if let Some(date) = week {
+        sched.set_week(date);
+    }
+    sched.set_key_role(Some("Keyholder"));
     sched.set_hours(Day::Saturday, 9, 21);
     sched.set_hours(Day::Sunday, 10, 18);
     sched.set_hours(Day::Tuesday, 10, 22);
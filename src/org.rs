@@ -0,0 +1,357 @@
+//! The org module ties several stores' schedules to one shared roster, for staff who float between locations.
+use super::emp::Roster;
+use super::gen::{self, Objective};
+use super::time::{Day, Schedule};
+use super::valid::Problem;
+use std::collections::HashMap;
+use std::fmt;
+
+// ==============================================
+
+/// A problem found across an organisation, naming the store it belongs to unless it spans all of them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrgProblem {
+    pub store: Option<String>,
+    pub problem: Problem,
+}
+
+impl fmt::Display for OrgProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.store {
+            Some(store) => write!(f, "[{}] {}", store, self.problem),
+            None => write!(f, "{}", self.problem),
+        }
+    }
+}
+
+// ==============================================
+
+/// A business running several stores, each with its own schedule, from one shared roster.
+///
+/// Employees' hours, availability and rest are checked across every store they work at, nobody may be in two
+/// stores at once, and moving between stores on the same day must leave time for the trip.
+pub struct Organisation {
+    pub roster: Roster,
+    stores: Vec<(String, Schedule)>,
+    travel: HashMap<(String, String), usize>,
+}
+
+impl Organisation {
+    // Constructor
+    pub fn new(roster: Roster) -> Organisation {
+        //! Create a new organisation with no stores.
+        Organisation {
+            roster,
+            stores: Vec::new(),
+            travel: HashMap::new(),
+        }
+    }
+    // Modification
+    pub fn add_store(&mut self, name: &str, sched: Schedule) {
+        //! Add a store and its schedule, replacing any store with the same name.
+        match self.stores.iter_mut().find(|(n, _)| n == name) {
+            Some((_, s)) => *s = sched,
+            None => self.stores.push((name.to_owned(), sched)),
+        }
+    }
+    pub fn set_travel(&mut self, a: &str, b: &str, minutes: usize) {
+        //! Set how long it takes to get between two stores, rounded up to the quarter-hour.
+        let qi = minutes.div_ceil(15);
+        self.travel.insert((a.to_owned(), b.to_owned()), qi);
+        self.travel.insert((b.to_owned(), a.to_owned()), qi);
+    }
+    // Access
+    pub fn store(&self, name: &str) -> Option<&Schedule> {
+        self.stores.iter().find(|(n, _)| n == name).map(|(_, s)| s)
+    }
+    pub fn store_mut(&mut self, name: &str) -> Option<&mut Schedule> {
+        self.stores
+            .iter_mut()
+            .find(|(n, _)| n == name)
+            .map(|(_, s)| s)
+    }
+    pub fn stores(&self) -> impl Iterator<Item = (&String, &Schedule)> {
+        //! Iterate over the stores in the order they were added.
+        self.stores.iter().map(|(n, s)| (n, s))
+    }
+    pub fn travel(&self, a: &str, b: &str) -> usize {
+        //! Return the travel time between two stores in quarter-hours, or 0 if none is set.
+        if a == b {
+            return 0;
+        }
+        *self.travel.get(&(a.to_owned(), b.to_owned())).unwrap_or(&0)
+    }
+    // Validation
+    fn spans_stores(p: &Problem) -> bool {
+        //! Check if a problem is about an employee's whole week across every store, rather than one store's rules.
+        matches!(
+            p,
+            Problem::Hours { .. } | Problem::Rest { .. } | Problem::DoubleBooked { .. }
+        )
+    }
+    fn merged(&self) -> Option<Schedule> {
+        let mut iter = self.stores.iter();
+        let mut out = iter.next()?.1.clone();
+        for (_, sched) in iter {
+            out.absorb_shifts(sched);
+        }
+        Some(out)
+    }
+    fn travel_problems(&self) -> Vec<Problem> {
+        let mut out = Vec::new();
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            let mut all = Vec::new();
+            for (name, sched) in self.stores.iter() {
+                for sh in sched.shifts(&day) {
+                    all.push((name, sh));
+                }
+            }
+            all.sort_by_key(|(_, sh)| (sh.emp_id.clone(), sh.start.get_qi()));
+            for pair in all.windows(2) {
+                let ((from, a), (to, b)) = (&pair[0], &pair[1]);
+                if a.emp_id != b.emp_id || from == to || b.start.get_qi() < a.end.get_qi() {
+                    continue;
+                }
+                let gap = b.start.get_qi() - a.end.get_qi();
+                let needed = self.travel(from, to);
+                if gap < needed {
+                    out.push(Problem::Travel {
+                        emp_id: a.emp_id.clone(),
+                        day: day.clone(),
+                        from: from.to_string(),
+                        to: to.to_string(),
+                        gap,
+                        needed,
+                    });
+                }
+            }
+        }
+        out
+    }
+    pub fn problems(&self) -> Vec<OrgProblem> {
+        //! List every rule broken across the organisation.
+        //!
+        //! Each store's shifts are checked against that store's own events, positions, rule packs and pay, and
+        //! the week as a whole for hours, rest and double-booking across stores.
+        let mut out = Vec::new();
+        if let Some(merged) = self.merged() {
+            for id in merged.emp_ids(&self.roster) {
+                for problem in merged.employee_problems(&id, &self.roster) {
+                    if Organisation::spans_stores(&problem) {
+                        out.push(OrgProblem {
+                            store: None,
+                            problem,
+                        });
+                    }
+                }
+            }
+        }
+        for problem in self.travel_problems() {
+            out.push(OrgProblem {
+                store: None,
+                problem,
+            });
+        }
+        for (name, sched) in self.stores.iter() {
+            for id in sched.emp_ids(&self.roster) {
+                for problem in sched.employee_problems(&id, &self.roster) {
+                    if !Organisation::spans_stores(&problem) {
+                        out.push(OrgProblem {
+                            store: Some(name.clone()),
+                            problem,
+                        });
+                    }
+                }
+            }
+            for problem in sched.store_problems(&self.roster) {
+                out.push(OrgProblem {
+                    store: Some(name.clone()),
+                    problem,
+                });
+            }
+        }
+        out
+    }
+    pub fn is_valid(&self) -> bool {
+        //! Check every store against every rule, printing any that are broken.
+        let problems = self.problems();
+        for p in problems.iter() {
+            println!("{}", p);
+        }
+        problems.is_empty()
+    }
+    // Generation
+    fn hard_problems(&self) -> Vec<OrgProblem> {
+        self.problems()
+            .into_iter()
+            .filter(|p| gen::is_hard(&p.problem))
+            .collect()
+    }
+    pub fn fill_shifts(&mut self, obj: &Objective) -> usize {
+        //! Fill every store's coverage gaps as `Schedule::fill_shifts` does, without breaking rules across stores.
+        //! Returns the number of shifts added.
+        let mut added = 0;
+        loop {
+            let before = self.hard_problems();
+            let mut candidates = Vec::new();
+            for (i, (_, sched)) in self.stores.iter_mut().enumerate() {
+                for (value, day, sh) in sched.candidates(&self.roster, obj) {
+                    candidates.push((value, i, day, sh));
+                }
+            }
+            candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+            let mut placed = 0;
+            for (_value, i, day, sh) in candidates {
+                let group = self.stores[i].1.with_partners(&sh, &self.roster);
+                let day_shifts = &mut self.stores[i].1.shifts[day.to_index()];
+                let len = day_shifts.len();
+                day_shifts.extend(group);
+                let known = |p: &OrgProblem| {
+                    let same_store = before.iter().filter(|was| was.store == p.store);
+                    gen::tolerates(&p.problem, same_store.map(|was| &was.problem))
+                };
+                if self.hard_problems().iter().all(known) {
+                    placed = self.stores[i].1.shifts[day.to_index()].len() - len;
+                    break;
+                }
+                self.stores[i].1.shifts[day.to_index()].truncate(len);
+            }
+            if placed == 0 {
+                break;
+            }
+            added += placed;
+        }
+        added
+    }
+}
+
+impl fmt::Display for Organisation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::new();
+        for (name, sched) in self.stores.iter() {
+            out.push_str(&format!("\n##### {} #####\n{}\n", name, sched));
+        }
+        write!(f, "{}", out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::time::Time;
+    fn setup() -> Organisation {
+        let (_, ros) = Fixture::new()
+            .employee("Flo", |em| {
+                em.hours().set(10, 12);
+            })
+            .build();
+        let mut org = Organisation::new(ros);
+        org.add_store("North", Schedule::new());
+        org.add_store("South", Schedule::new());
+        org.set_travel("North", "South", 40);
+        org
+    }
+    #[test]
+    fn hours_across_stores() {
+        let mut org = setup();
+        let north = org.store_mut("North").unwrap();
+        north.assign_shift(
+            "Flo".to_string(),
+            Day::Saturday,
+            Time::from_hour(9),
+            Time::from_hour(15),
+        );
+        assert!(org
+            .store("North")
+            .unwrap()
+            .problems(&org.roster)
+            .iter()
+            .any(|p| matches!(p, Problem::Hours { .. })));
+        let south = org.store_mut("South").unwrap();
+        south.assign_shift(
+            "Flo".to_string(),
+            Day::Sunday,
+            Time::from_hour(9),
+            Time::from_hour(15),
+        );
+        assert!(org.problems().is_empty());
+    }
+    #[test]
+    fn travel_and_overlap() {
+        let mut org = setup();
+        let north = org.store_mut("North").unwrap();
+        north.assign_shift(
+            "Flo".to_string(),
+            Day::Saturday,
+            Time::from_hour(9),
+            Time::from_hour(14),
+        );
+        let south = org.store_mut("South").unwrap();
+        south.assign_shift(
+            "Flo".to_string(),
+            Day::Saturday,
+            Time::from_str("14:30"),
+            Time::from_hour(20),
+        );
+        let problems = org.problems();
+        eprintln!("{:?}", problems);
+        assert_eq!(problems.len(), 1);
+        assert!(matches!(
+            problems[0].problem,
+            Problem::Travel {
+                gap: 2,
+                needed: 3,
+                ..
+            }
+        ));
+        let south = org.store_mut("South").unwrap();
+        south.assign_shift(
+            "Flo".to_string(),
+            Day::Saturday,
+            Time::from_hour(12),
+            Time::from_hour(13),
+        );
+        assert!(org
+            .problems()
+            .iter()
+            .any(|p| matches!(p.problem, Problem::DoubleBooked { .. })));
+    }
+    #[test]
+    fn own_rules() {
+        let mut org = setup();
+        let south = org.store_mut("South").unwrap();
+        south.add_event(
+            "League",
+            "Pokemon",
+            Day::Sunday,
+            Time::from_hour(12),
+            Time::from_hour(15),
+        );
+        let ev = south.events[0].clone();
+        south.assign_event("Flo".to_string(), ev);
+        // South's event is checked against South's schedule, and Flo's hours against the whole week.
+        let problems = org.problems();
+        assert_eq!(problems.len(), 2);
+        assert!(matches!(problems[0].problem, Problem::Hours { .. }));
+        assert_eq!(problems[1].store, Some("South".to_string()));
+        assert!(matches!(problems[1].problem, Problem::Unable { .. }));
+    }
+    #[test]
+    fn fill_across_stores() {
+        let mut org = setup();
+        for name in ["North", "South"].iter() {
+            let sched = org.store_mut(name).unwrap();
+            for qi in 40..56 {
+                sched.set_req(&Day::Saturday, qi, 1);
+            }
+        }
+        org.fill_shifts(&Objective::default());
+        let placed = org
+            .stores()
+            .filter(|(_, s)| !s.shifts(&Day::Saturday).is_empty())
+            .count();
+        assert_eq!(placed, 1);
+    }
+}
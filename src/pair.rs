@@ -0,0 +1,158 @@
+//! The pair module holds rules about which employees must, or must not, work together.
+use super::emp::{Employee, Roster};
+use super::time::{Day, Schedule, Shift};
+use super::valid::Problem;
+#[cfg(feature = "project")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// ==============================================
+
+/// A rule between two employees, added with `Roster::add_pairing`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub enum Pairing {
+    /// The two must work exactly matching shifts, or both be off.
+    Together(String, String),
+    /// The two must never be on the clock at the same time.
+    Apart(String, String),
+}
+
+impl Pairing {
+    // Access
+    pub fn involves(&self, id: &str) -> bool {
+        self.partner_of(id).is_some()
+    }
+    pub fn partner_of(&self, id: &str) -> Option<&str> {
+        //! Return whoever this rule pairs with `id`, if it names them.
+        let (a, b) = match self {
+            Pairing::Together(a, b) | Pairing::Apart(a, b) => (a, b),
+        };
+        if a == id {
+            Some(b)
+        } else if b == id {
+            Some(a)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Pairing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pairing::Together(a, b) => write!(f, "{} and {} work together", a, b),
+            Pairing::Apart(a, b) => write!(f, "{} and {} work apart", a, b),
+        }
+    }
+}
+
+// ==============================================
+
+impl Schedule {
+    fn spans_of(&self, day: &Day, id: &str) -> Vec<(usize, usize)> {
+        let mut out: Vec<_> = self
+            .shifts(day)
+            .iter()
+            .filter(|sh| sh.emp_id == id)
+            .map(|sh| (sh.start.get_qi(), sh.end.get_qi()))
+            .collect();
+        out.sort();
+        out
+    }
+    // Validation
+    pub fn pairing_problems(&self, em: &Employee, ros: &Roster) -> Vec<Problem> {
+        //! List every day on which an employee breaks a together or apart rule.
+        let id = em.id();
+        let mut out = Vec::new();
+        for pairing in ros.pairings().iter().filter(|p| p.involves(&id)) {
+            let other = pairing.partner_of(&id).unwrap();
+            for i in 0..7 {
+                let day = Day::from_index(i).unwrap();
+                let mine = self.spans_of(&day, &id);
+                let theirs = self.spans_of(&day, other);
+                let broken = match pairing {
+                    Pairing::Together(..) => mine != theirs,
+                    Pairing::Apart(..) => mine
+                        .iter()
+                        .any(|(s, e)| theirs.iter().any(|(os, oe)| s < oe && os < e)),
+                };
+                if broken {
+                    out.push(Problem::Pairing {
+                        emp_id: id.clone(),
+                        day,
+                        rule: pairing.to_string(),
+                    });
+                }
+            }
+        }
+        out
+    }
+    // Generation
+    pub(crate) fn with_partners(&self, sh: &Shift, ros: &Roster) -> Vec<Shift> {
+        //! Return a shift along with matching shifts for everyone who must work together with its employee.
+        let mut out = vec![sh.clone()];
+        let mut i = 0;
+        while i < out.len() {
+            let id = out[i].emp_id.clone();
+            for pairing in ros.pairings() {
+                if let (Pairing::Together(..), Some(other)) = (pairing, pairing.partner_of(&id)) {
+                    if !out.iter().any(|s| s.emp_id == other) {
+                        out.push(Shift::new(
+                            other.to_owned(),
+                            sh.start.clone(),
+                            sh.end.clone(),
+                        ));
+                    }
+                }
+            }
+            i += 1;
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::gen::Objective;
+    use crate::time::Time;
+    fn setup() -> Fixture {
+        Fixture::new()
+            .staff(&["Al", "Bea", "Cal"])
+            .pairing(Pairing::Together("Al".to_string(), "Bea".to_string()))
+            .pairing(Pairing::Apart("Al".to_string(), "Cal".to_string()))
+            .need(Day::Saturday, "10:00", "18:00", 1)
+    }
+    #[test]
+    fn together_and_apart() {
+        let (mut sched, ros) = setup()
+            .shift("Al", Day::Saturday, "10:00", "14:00")
+            .shift("Cal", Day::Saturday, "13:00", "18:00")
+            .build();
+        let al = ros.get("Al").unwrap();
+        assert_eq!(sched.pairing_problems(al, &ros).len(), 2);
+        sched.assign_shift(
+            "Bea".to_string(),
+            Day::Saturday,
+            Time::from_hour(10),
+            Time::from_hour(14),
+        );
+        assert_eq!(sched.pairing_problems(al, &ros).len(), 1);
+    }
+    #[test]
+    fn generation_honours_pairs() {
+        let (mut sched, ros) = setup().build();
+        sched.fill_shifts(&ros, &Objective::default());
+        let sat = sched.shifts(&Day::Saturday);
+        assert!(!sat.is_empty());
+        for id in ["Al", "Bea", "Cal"].iter() {
+            let em = ros.get(id).unwrap();
+            assert!(sched.pairing_problems(em, &ros).is_empty());
+        }
+        let al = sat.iter().filter(|sh| sh.emp_id == "Al").count();
+        let bea = sat.iter().filter(|sh| sh.emp_id == "Bea").count();
+        assert_eq!(al, bea);
+    }
+}
@@ -0,0 +1,391 @@
+//! The project module saves a whole store, its roster and its schedule, to one JSON or TOML file and loads it back.
+//!
+//! Only built with the `project` feature.
+//!
+//! Every project file records the format version it was written in. Files from older versions are upgraded as
+//! they load, one version at a time, by the steps in `MIGRATIONS`.
+use super::emp::Roster;
+use super::time::{Day, Schedule, Time, Window};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// The version of the project file format written by this build.
+///
+/// 1. The first format.
+/// 2. Time windows are written as "H:MM-H:MM" text, and availability is keyed by weekday name.
+/// 3. Schedules record the store's open hours for each day.
+pub const VERSION: u32 = 3;
+
+/// A step that upgrades a project file, parsed but not yet read into a `Project`, from one version to the next.
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// The upgrade from each older version, in order: the first entry upgrades version 1 to version 2.
+const MIGRATIONS: [Migration; 2] = [windows_as_text, record_open_hours];
+
+// ==============================================
+
+/// Everything needed to pick up work on a store's week where it was left off.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub version: u32,
+    pub roster: Roster,
+    pub schedule: Schedule,
+}
+
+impl Project {
+    // Constructor
+    pub fn new(roster: Roster, schedule: Schedule) -> Project {
+        //! Create a project at the current file format version.
+        Project {
+            version: VERSION,
+            roster,
+            schedule,
+        }
+    }
+    // Saving
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+    pub fn to_toml(&self) -> Result<String, Box<dyn Error>> {
+        Ok(toml::to_string(self)?)
+    }
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        //! Write this project to a file, as TOML if its name ends in `.toml` and as JSON otherwise.
+        let out = if is_toml(path) {
+            self.to_toml()?
+        } else {
+            self.to_json()?
+        };
+        fs::write(path, out)?;
+        Ok(())
+    }
+    // Loading
+    pub fn from_json(s: &str) -> Result<Project, Box<dyn Error>> {
+        Project::migrated(serde_json::from_str(s)?)
+    }
+    pub fn from_toml(s: &str) -> Result<Project, Box<dyn Error>> {
+        let value: toml::Value = toml::from_str(s)?;
+        Project::migrated(serde_json::to_value(value)?)
+    }
+    pub fn load(path: &Path) -> Result<Project, Box<dyn Error>> {
+        //! Read a project from a file, as TOML if its name ends in `.toml` and as JSON otherwise, upgrading it
+        //! from an older version if need be.
+        let s = fs::read_to_string(path)?;
+        if is_toml(path) {
+            Project::from_toml(&s)
+        } else {
+            Project::from_json(&s)
+        }
+    }
+    fn migrated(mut value: Value) -> Result<Project, Box<dyn Error>> {
+        //! Upgrade a parsed project file to the current version and read it.
+        upgrade(&mut value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+pub(crate) fn upgrade(value: &mut Value) -> Result<(), Box<dyn Error>> {
+    //! Upgrade a parsed project file to the current version, step by step, in place. Anything else stored in
+    //! the project file format can be upgraded by nesting it where it would sit in a project file.
+    let version = match value.get("version").and_then(Value::as_u64) {
+        Some(v) => v as u32,
+        None => return Err("not a project file: it has no version number".into()),
+    };
+    if version == 0 || version > VERSION {
+        return Err(format!(
+            "project file is version {}, but this build only reads versions 1 to {}",
+            version, VERSION
+        )
+        .into());
+    }
+    for (from, step) in (version..VERSION).zip(MIGRATIONS[version as usize - 1..].iter()) {
+        step(value).map_err(|e| {
+            format!(
+                "can't upgrade project file from version {} to {}: {}",
+                from,
+                from + 1,
+                e
+            )
+        })?;
+        value["version"] = Value::from(from + 1);
+    }
+    Ok(())
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+}
+
+// ==============================================
+
+// Times are written as "HH:MM" so project files stay readable and editable by hand.
+impl From<Time> for String {
+    fn from(t: Time) -> String {
+        t.to_string_24h()
+    }
+}
+
+impl TryFrom<String> for Time {
+    type Error = String;
+    fn try_from(s: String) -> Result<Time, String> {
+        Time::parse(&s).ok_or_else(|| format!("bad time \"{}\"", s))
+    }
+}
+
+// Windows are written as "H:MM-H:MM" for the same reason.
+impl From<Window> for String {
+    fn from(w: Window) -> String {
+        format!("{}-{}", w.start.to_string_24h(), w.end.to_string_24h())
+    }
+}
+
+impl TryFrom<String> for Window {
+    type Error = String;
+    fn try_from(s: String) -> Result<Window, String> {
+        Window::from_str(&s).ok_or_else(|| format!("bad time window \"{}\"", s))
+    }
+}
+
+pub(crate) fn sorted<S: Serializer, V: Serialize>(
+    map: &HashMap<String, V>,
+    ser: S,
+) -> Result<S::Ok, S::Error> {
+    //! Write a map in key order, so that saving the same project twice gives the same file.
+    map.iter().collect::<BTreeMap<_, _>>().serialize(ser)
+}
+
+/// Staffing requirements by day and quarter-hour, which serde can't handle as fixed-size arrays this long.
+pub(crate) mod reqs {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(reqs: &[[i32; 24 * 4]; 7], ser: S) -> Result<S::Ok, S::Error> {
+        let days: Vec<&[i32]> = reqs.iter().map(|day| &day[..]).collect();
+        days.serialize(ser)
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<[[i32; 24 * 4]; 7], D::Error> {
+        let days: Vec<Vec<i32>> = Vec::deserialize(de)?;
+        if days.len() != 7 || days.iter().any(|day| day.len() != 24 * 4) {
+            return Err(D::Error::custom(
+                "requirements must be 7 days of 96 quarter-hours",
+            ));
+        }
+        let mut out = [[0; 24 * 4]; 7];
+        for (i, day) in days.iter().enumerate() {
+            out[i].copy_from_slice(day);
+        }
+        Ok(out)
+    }
+}
+
+/// Availability by weekday name, so hand-edited files needn't count days.
+pub(crate) mod avail {
+    use crate::time::{Day, Window};
+    use serde::de::Error;
+    use serde::ser::SerializeMap;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(avail: &[Vec<Window>; 7], ser: S) -> Result<S::Ok, S::Error> {
+        let mut map = ser.serialize_map(Some(7))?;
+        for (i, windows) in avail.iter().enumerate() {
+            map.serialize_entry(&Day::from_index(i).unwrap().to_string(), windows)?;
+        }
+        map.end()
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<[Vec<Window>; 7], D::Error> {
+        let mut days: HashMap<String, Vec<Window>> = HashMap::deserialize(de)?;
+        let mut out: [Vec<Window>; 7] = Default::default();
+        for (i, windows) in out.iter_mut().enumerate() {
+            let day = Day::from_index(i).unwrap().to_string();
+            *windows = days
+                .remove(&day)
+                .ok_or_else(|| D::Error::custom(format!("availability has no {}", day)))?;
+        }
+        if let Some(day) = days.keys().next() {
+            return Err(D::Error::custom(format!("\"{}\" is not a weekday", day)));
+        }
+        Ok(out)
+    }
+}
+
+/// Reads and writes a schedule's open hours as a map from weekday name to "H:MM-H:MM", leaving out days
+/// without set hours.
+pub(crate) mod open_hours {
+    use crate::time::{Day, Time, Window};
+    use serde::de::Error;
+    use serde::ser::SerializeMap;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        hours: &[Option<(usize, usize)>; 7],
+        ser: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = ser.serialize_map(None)?;
+        for (i, day_hours) in hours.iter().enumerate() {
+            if let Some((open, close)) = day_hours {
+                let window = Window::new(Time::from_qi(*open), Time::from_qi(*close));
+                map.serialize_entry(&Day::from_index(i).unwrap().to_string(), &window)?;
+            }
+        }
+        map.end()
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        de: D,
+    ) -> Result<[Option<(usize, usize)>; 7], D::Error> {
+        let days: HashMap<String, Window> = HashMap::deserialize(de)?;
+        let mut out = [None; 7];
+        for (day, window) in days {
+            let i = Day::from_str(&day)
+                .ok_or_else(|| D::Error::custom(format!("\"{}\" is not a weekday", day)))?
+                .to_index();
+            out[i] = Some((window.start.get_qi(), window.end.get_qi()));
+        }
+        Ok(out)
+    }
+}
+
+// ==============================================
+
+fn windows_as_text(value: &mut Value) -> Result<(), String> {
+    //! Version 1 to 2: write every time window as "H:MM-H:MM" and key availability by weekday name.
+    fn rewrite(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                let is_window = map.len() == 2
+                    && map.get("start").is_some_and(Value::is_string)
+                    && map.get("end").is_some_and(Value::is_string);
+                if is_window {
+                    let text = format!(
+                        "{}-{}",
+                        map["start"].as_str().unwrap(),
+                        map["end"].as_str().unwrap()
+                    );
+                    *value = Value::String(text);
+                } else {
+                    map.values_mut().for_each(rewrite);
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(rewrite),
+            _ => (),
+        }
+    }
+    rewrite(value);
+    let emps = match value.pointer_mut("/roster/emps") {
+        Some(Value::Object(emps)) => emps,
+        _ => return Err("roster has no employees table".to_string()),
+    };
+    for (id, em) in emps.iter_mut() {
+        let days = match em.get_mut("avail").map(Value::take) {
+            Some(Value::Array(days)) if days.len() == 7 => days,
+            _ => return Err(format!("availability for {} isn't a list of 7 days", id)),
+        };
+        let mut by_name = Map::new();
+        for (i, windows) in days.into_iter().enumerate() {
+            by_name.insert(Day::from_index(i).unwrap().to_string(), windows);
+        }
+        em["avail"] = Value::Object(by_name);
+    }
+    Ok(())
+}
+
+fn record_open_hours(value: &mut Value) -> Result<(), String> {
+    //! Version 2 to 3: give every schedule an empty table of open hours, as older versions didn't keep them.
+    fn rewrite(value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                if map.contains_key("raw_reqs") {
+                    map.entry("open_hours")
+                        .or_insert_with(|| Value::Object(Map::new()));
+                }
+                map.values_mut().for_each(rewrite);
+            }
+            Value::Array(items) => items.iter_mut().for_each(rewrite),
+            _ => (),
+        }
+    }
+    rewrite(value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emp::{Preference, TimeOff};
+    use crate::fixture::Fixture;
+    use crate::law::RulePack;
+    use crate::time::{Day, Window};
+    use chrono::NaiveDate;
+    fn setup() -> Project {
+        let (mut sched, ros) = Fixture::new()
+            .employee("Matt", |em| {
+                em.set_abil("Magic", 4);
+                em.add_role("Manager");
+                em.set_dob(NaiveDate::from_ymd_opt(1990, 4, 1));
+                em.add_preference(Preference::ShiftLength(4, 8), 2.0);
+                em.set_windows(Day::Tuesday, vec![Window::from_str("15:00-23:45").unwrap()]);
+                let day = NaiveDate::from_ymd_opt(2019, 6, 10).unwrap();
+                em.request_time_off(TimeOff::new(day, day));
+            })
+            .dated()
+            .event("FNM", "Magic", Day::Friday, "18:00", "22:00")
+            .shift("Matt", Day::Saturday, "9:45", "17:30")
+            .build();
+        sched.events[0].staffing_req(2);
+        sched.set_hours(Day::Saturday, 10, 18);
+        sched.add_rule_pack(RulePack::builtin("minors").unwrap());
+        Project::new(ros, sched)
+    }
+    #[test]
+    fn json_round_trip() {
+        let project = setup();
+        let json = project.to_json().unwrap();
+        assert!(json.contains("\"9:45\""));
+        assert_eq!(Project::from_json(&json).unwrap(), project);
+    }
+    #[test]
+    fn toml_round_trip() {
+        let project = setup();
+        let toml = project.to_toml().unwrap();
+        assert_eq!(Project::from_toml(&toml).unwrap(), project);
+        let newer = toml.replacen(&format!("version = {}", VERSION), "version = 99", 1);
+        assert!(Project::from_toml(&newer).is_err());
+    }
+    #[test]
+    fn upgrades_version_1() {
+        let v1 = include_str!("../tests/data/project_v1.json");
+        let project = Project::from_json(v1).unwrap();
+        assert_eq!(project.version, VERSION);
+        let matt = project.roster.get("Matt").unwrap();
+        assert_eq!(matt.rate(), Some(15.0));
+        assert_eq!(
+            matt.windows(&Day::Tuesday),
+            &[Window::from_str("15:00-23:45").unwrap()]
+        );
+        let date = NaiveDate::from_ymd_opt(2019, 6, 10).unwrap();
+        let off = TimeOff::partial(date, Window::from_str("9:00-12:00").unwrap());
+        assert_eq!(matt.time_off(), &[off]);
+        assert_eq!(project.schedule.wages().overtime_after(), 40);
+        assert_eq!(project.schedule.shifts(&Day::Saturday).len(), 1);
+        assert_eq!(project.schedule.open_hours(&Day::Saturday), None);
+        // Saving again writes the current version, which loads unchanged.
+        let json = project.to_json().unwrap();
+        assert!(json.contains("\"Tuesday\": [\n"));
+        assert_eq!(Project::from_json(&json).unwrap(), project);
+    }
+    #[test]
+    fn unconvertible() {
+        let err = |s: &str| Project::from_json(s).unwrap_err().to_string();
+        assert!(err("{}").contains("no version"));
+        assert!(err(r#"{"version": 0}"#).contains("versions 1 to"));
+        let v1 = include_str!("../tests/data/project_v1.json");
+        let broken = v1.replacen(r#""avail":[["#, r#""avail":[[],["#, 1);
+        assert!(err(&broken).contains("from version 1 to 2: availability for Matt"));
+    }
+}
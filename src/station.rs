@@ -0,0 +1,317 @@
+//! The station module covers positions within a store (register, floor, judge...) and their separate staffing.
+use super::emp::{Employee, Roster};
+use super::time::{Day, Schedule, Time, Window};
+use super::valid::{Problem, ShiftKey};
+#[cfg(feature = "project")]
+use serde::{Deserialize, Serialize};
+
+// ==============================================
+
+/// A position staff can be posted to within a store, with its own staffing requirements.
+///
+/// Anyone may take a position unless it requires roles (any one of which will do) or a minimum ability.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub struct Position {
+    pub name: String,
+    roles: Vec<String>,
+    ability: Option<(String, u8)>,
+    #[cfg_attr(feature = "project", serde(with = "crate::project::reqs"))]
+    reqs: [[i32; 24 * 4]; 7],
+}
+
+impl Position {
+    // Constructor
+    pub fn new(name: &str) -> Position {
+        //! Create a new position that anyone may take and that needs nobody.
+        Position {
+            name: name.to_owned(),
+            roles: Vec::new(),
+            ability: None,
+            reqs: [[0; 24 * 4]; 7],
+        }
+    }
+    // Modification
+    pub fn require_role(&mut self, role: &str) -> &mut Position {
+        //! Allow employees with this role to take the position. Once any role is given, only they may.
+        self.roles.push(role.to_owned());
+        self
+    }
+    pub fn require_ability(&mut self, kind: &str, min: u8) -> &mut Position {
+        //! Only allow employees with at least `min` ability at `kind` to take the position.
+        self.ability = Some((kind.to_owned(), min));
+        self
+    }
+    pub fn set_req(&mut self, day: &Day, window: &Window, num_emps: i32) -> &mut Position {
+        //! Require `num_emps` staff at this position throughout a window of a day.
+        for qi in window.start.get_qi()..window.end.get_qi() {
+            self.reqs[day.to_index()][qi] = num_emps;
+        }
+        self
+    }
+    // Access
+    pub fn get_req(&self, day: &Day, qi: usize) -> i32 {
+        self.reqs[day.to_index()][qi]
+    }
+    pub fn roles(&self) -> &[String] {
+        &self.roles
+    }
+    pub fn ability(&self) -> Option<&(String, u8)> {
+        self.ability.as_ref()
+    }
+    pub fn is_eligible(&self, em: &Employee) -> bool {
+        //! Check if an employee may take this position.
+        let role_ok = self.roles.is_empty() || self.roles.iter().any(|r| em.has_role(r));
+        let abil_ok = match &self.ability {
+            Some((kind, min)) => em.get_abil(kind).is_some_and(|a| a >= *min),
+            None => true,
+        };
+        role_ok && abil_ok
+    }
+}
+
+// ==============================================
+
+impl Schedule {
+    // Modification
+    pub fn add_position(&mut self, pos: Position) -> &mut Position {
+        //! Add a position to this store, replacing any position with the same name.
+        self.positions.retain(|p| p.name != pos.name);
+        self.positions.push(pos);
+        let li = self.positions.len() - 1;
+        &mut self.positions[li]
+    }
+    pub fn position_mut(&mut self, name: &str) -> Option<&mut Position> {
+        self.positions.iter_mut().find(|p| p.name == name)
+    }
+    pub fn set_position(&mut self, key: &ShiftKey, position: &str, during: Option<Window>) -> bool {
+        //! Post a shift to a position, either for the whole shift or only `during` part of it.
+        //! Returns false if there is no such shift or position, or if `during` isn't within the shift.
+        if self.position(position).is_none() {
+            return false;
+        }
+        let shifts = &mut self.shifts[key.day.to_index()];
+        match shifts
+            .iter_mut()
+            .find(|sh| sh.emp_id == key.emp_id && sh.start == key.start)
+        {
+            Some(sh) => {
+                if let Some(w) = &during {
+                    let (s, e) = (w.start.get_qi(), w.end.get_qi());
+                    if s >= e || s < sh.start.get_qi() || e > sh.end.get_qi() {
+                        return false;
+                    }
+                }
+                match during {
+                    Some(w) => sh.segments.push((w, position.to_owned())),
+                    None => sh.position = Some(position.to_owned()),
+                }
+                true
+            }
+            None => false,
+        }
+    }
+    // Access
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+    pub fn position(&self, name: &str) -> Option<&Position> {
+        self.positions.iter().find(|p| p.name == name)
+    }
+    pub fn position_coverage(&self, day: &Day, name: &str) -> [i32; 24 * 4] {
+        //! Return the staff posted to a position during each quarter-hour of a day.
+        let mut out = [0; 24 * 4];
+        for sh in self.shifts(day) {
+            for (qi, cover) in out
+                .iter_mut()
+                .enumerate()
+                .take(sh.end.get_qi())
+                .skip(sh.start.get_qi())
+            {
+                if sh.position_at(qi) == Some(name) {
+                    *cover += 1;
+                }
+            }
+        }
+        out
+    }
+    pub(crate) fn position_gaps(&self, day: &Day, ros: &Roster) -> Vec<(String, usize)> {
+        //! List each position and quarter-hour of a day in which fewer staff able to take the position are on
+        //! hand than it requires, whoever they are posted to. Generation uses this before anyone is posted.
+        let mut out = Vec::new();
+        for pos in self.positions.iter() {
+            for qi in 0..24 * 4 {
+                let need = pos.get_req(day, qi);
+                if need == 0 {
+                    continue;
+                }
+                let able = self
+                    .shifts(day)
+                    .iter()
+                    .filter(|sh| sh.event.is_none())
+                    .filter(|sh| sh.start.get_qi() <= qi && qi < sh.end.get_qi())
+                    .filter(|sh| ros.get(&sh.emp_id).is_some_and(|em| pos.is_eligible(em)))
+                    .count();
+                if (able as i32) < need {
+                    out.push((pos.name.clone(), qi));
+                }
+            }
+        }
+        out
+    }
+    // Validation
+    pub fn position_problems(&self) -> Vec<Problem> {
+        //! List every quarter-hour in which a position has fewer staff than it requires.
+        let mut out = Vec::new();
+        for pos in self.positions.iter() {
+            for i in 0..7 {
+                let day = Day::from_index(i).unwrap();
+                let coverage = self.position_coverage(&day, &pos.name);
+                for (qi, have) in coverage.iter().enumerate() {
+                    let need = pos.get_req(&day, qi);
+                    if *have < need {
+                        out.push(Problem::PositionCoverage {
+                            position: pos.name.clone(),
+                            day: day.clone(),
+                            time: Time::from_qi(qi),
+                            have: *have,
+                            need,
+                        });
+                    }
+                }
+            }
+        }
+        out
+    }
+    pub fn eligibility_problems(&self, em: &Employee) -> Vec<Problem> {
+        //! List every position an employee is posted to without being eligible for it.
+        let mut out = Vec::new();
+        for (day, sh) in self.shifts_of(&em.id()) {
+            let mut names: Vec<&String> = sh.segments.iter().map(|(_, p)| p).collect();
+            names.extend(sh.position.iter());
+            for name in names {
+                if let Some(pos) = self.position(name) {
+                    if !pos.is_eligible(em) {
+                        out.push(Problem::Ineligible {
+                            emp_id: em.id(),
+                            day: day.clone(),
+                            position: name.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        out
+    }
+    // Generation
+    pub fn assign_positions(&mut self, ros: &Roster) -> usize {
+        //! Post every shift without a position to whichever eligible position it helps staff the most.
+        //! Returns the number of shifts posted.
+        let mut posted = 0;
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            for j in 0..self.shifts[i].len() {
+                let sh = &self.shifts[i][j];
+                if sh.position.is_some() || !sh.segments.is_empty() {
+                    continue;
+                }
+                let em = match ros.iter().find(|(k, _)| **k == sh.emp_id) {
+                    Some((_, em)) => em,
+                    None => continue,
+                };
+                let mut best: Option<(usize, String)> = None;
+                for pos in self.positions.iter().filter(|p| p.is_eligible(em)) {
+                    let coverage = self.position_coverage(&day, &pos.name);
+                    let helped = (sh.start.get_qi()..sh.end.get_qi())
+                        .filter(|qi| coverage[*qi] < pos.get_req(&day, *qi))
+                        .count();
+                    if helped > 0 && best.as_ref().is_none_or(|(b, _)| helped > *b) {
+                        best = Some((helped, pos.name.clone()));
+                    }
+                }
+                if let Some((_, name)) = best {
+                    self.shifts[i][j].position = Some(name);
+                    posted += 1;
+                }
+            }
+        }
+        posted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::gen::Objective;
+    fn setup() -> (Schedule, Roster) {
+        let (mut sched, ros) = Fixture::new()
+            .employee("Jo", |em| em.set_abil("Magic", 4))
+            .employee("Cy", |em| em.add_role("Associate"))
+            .build();
+        let all = Window::new(Time::from_hour(12), Time::from_hour(16));
+        sched
+            .add_position(Position::new("Register"))
+            .require_role("Associate")
+            .set_req(&Day::Saturday, &all, 1);
+        sched
+            .add_position(Position::new("Judge"))
+            .require_ability("Magic", 3)
+            .set_req(&Day::Saturday, &all, 1);
+        (sched, ros)
+    }
+    #[test]
+    fn posts_and_coverage() {
+        let (mut sched, ros) = setup();
+        sched.assign_shift(
+            "Jo".to_string(),
+            Day::Saturday,
+            Time::from_hour(12),
+            Time::from_hour(16),
+        );
+        sched.assign_shift(
+            "Cy".to_string(),
+            Day::Saturday,
+            Time::from_hour(12),
+            Time::from_hour(16),
+        );
+        assert_eq!(sched.position_problems().len(), 32);
+        assert_eq!(sched.assign_positions(&ros), 2);
+        assert!(sched.position_problems().is_empty());
+        assert_eq!(
+            sched.shifts(&Day::Saturday)[0].position,
+            Some("Judge".to_string())
+        );
+    }
+    #[test]
+    fn segments_and_eligibility() {
+        let (mut sched, ros) = setup();
+        sched.assign_shift(
+            "Cy".to_string(),
+            Day::Saturday,
+            Time::from_hour(12),
+            Time::from_hour(16),
+        );
+        let key = ShiftKey::new("Cy", Day::Saturday, Time::from_hour(12));
+        assert!(sched.set_position(&key, "Register", None));
+        assert!(sched.set_position(&key, "Judge", Window::from_str("14:00-15:00")));
+        assert!(!sched.set_position(&key, "Floor", None));
+        assert!(!sched.set_position(&key, "Judge", Window::from_str("11:00-13:00")));
+        assert_eq!(sched.position_coverage(&Day::Saturday, "Register")[56], 0);
+        assert_eq!(sched.position_coverage(&Day::Saturday, "Judge")[56], 1);
+        let cy = ros.get("Cy").unwrap();
+        assert_eq!(sched.eligibility_problems(cy).len(), 1);
+    }
+    #[test]
+    fn generation_fills_positions() {
+        let (mut sched, ros) = setup();
+        for qi in 48..64 {
+            sched.set_req(&Day::Saturday, qi, 1);
+        }
+        // One person meets the headcount, but only Jo can judge and only Cy can take the register.
+        sched.fill_shifts(&ros, &Objective::default());
+        assert_eq!(sched.shifts(&Day::Saturday).len(), 2);
+        assert_eq!(sched.assign_positions(&ros), 2);
+        assert!(sched.position_problems().is_empty());
+    }
+}
@@ -0,0 +1,488 @@
+//! The store module keeps a store's roster, setup and every published schedule in a SQLite database, so past
+//! weeks can be looked up and questions like "how many Saturdays did Bob work this quarter?" answered.
+//!
+//! Only built with the `sqlite` feature.
+//!
+//! Employees, events, setup and schedules are stored as JSON in the project file format, tagged with the
+//! version they were written in, and upgraded by the project file migrations when an older build wrote them.
+//! Published shifts are also kept one per row so history can be queried without reading whole schedules.
+use super::emp::{Employee, Roster};
+use super::pair::Pairing;
+use super::project::{self, VERSION};
+use super::time::{Day, Event, Schedule};
+use chrono::{Datelike, Duration, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::path::Path;
+
+/// The version of the database layout written by this build, kept in SQLite's `user_version`.
+///
+/// 1. The first layout.
+pub const SCHEMA_VERSION: usize = 1;
+
+/// The statements that upgrade the database layout from each older version, in order: the first entry sets up
+/// an empty database.
+const SCHEMA: [&str; 1] = ["
+    CREATE TABLE employees (
+        id TEXT PRIMARY KEY,
+        format INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE pairings (
+        format INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE events (
+        name TEXT NOT NULL,
+        day INTEGER NOT NULL,
+        format INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE setup (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        format INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE schedules (
+        week TEXT PRIMARY KEY,
+        published TEXT NOT NULL,
+        format INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE shifts (
+        week TEXT NOT NULL REFERENCES schedules (week) ON DELETE CASCADE,
+        date TEXT NOT NULL,
+        day INTEGER NOT NULL,
+        emp_id TEXT NOT NULL,
+        start INTEGER NOT NULL,
+        end INTEGER NOT NULL,
+        event TEXT,
+        position TEXT
+    );
+    CREATE INDEX shifts_by_employee ON shifts (emp_id, date);
+"];
+
+const DATE: &str = "%Y-%m-%d";
+
+// ==============================================
+
+fn date_text(date: NaiveDate) -> String {
+    date.format(DATE).to_string()
+}
+
+fn week_of(date: NaiveDate) -> NaiveDate {
+    //! Return the Saturday starting the week that `date` falls in.
+    date - Duration::days(Day::of(date).to_index() as i64)
+}
+
+pub fn quarter(date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    //! Return the first and last days of the calendar quarter containing `date`.
+    //!
+    //! # Examples
+    //! ```
+    //! use chrono::NaiveDate;
+    //! use makeshift::store::quarter;
+    //! let (first, last) = quarter(NaiveDate::from_ymd_opt(2019, 6, 8).unwrap());
+    //! assert_eq!(first, NaiveDate::from_ymd_opt(2019, 4, 1).unwrap());
+    //! assert_eq!(last, NaiveDate::from_ymd_opt(2019, 6, 30).unwrap());
+    //! ```
+    let month = (date.month0() / 3) * 3 + 1;
+    let first = NaiveDate::from_ymd_opt(date.year(), month, 1).unwrap();
+    let next = match month {
+        10 => NaiveDate::from_ymd_opt(date.year() + 1, 1, 1),
+        _ => NaiveDate::from_ymd_opt(date.year(), month + 3, 1),
+    };
+    (first, next.unwrap() - Duration::days(1))
+}
+
+fn decode<T: DeserializeOwned>(
+    format: u32,
+    data: &str,
+    emp_id: Option<&str>,
+) -> Result<T, Box<dyn Error>> {
+    //! Read a stored blob, upgrading it first if it was written in an older format. The blob is nested where it
+    //! would sit in a project file, under the roster if it is an employee, so the project file upgrades apply.
+    let value: Value = serde_json::from_str(data)?;
+    if format == VERSION {
+        return Ok(serde_json::from_value(value)?);
+    }
+    let mut doc = json!({ "version": format, "roster": { "emps": {} } });
+    match emp_id {
+        Some(id) => doc["roster"]["emps"][id] = value,
+        None => doc["item"] = value,
+    }
+    project::upgrade(&mut doc)?;
+    let value = match emp_id {
+        Some(id) => doc["roster"]["emps"][id].take(),
+        None => doc["item"].take(),
+    };
+    Ok(serde_json::from_value(value)?)
+}
+
+// ==============================================
+
+/// A SQLite database holding a store's roster, setup and schedule history.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    // Constructors
+    pub fn open(path: &Path) -> Result<Store, Box<dyn Error>> {
+        //! Open the database at `path`, creating it if it doesn't exist and upgrading its layout if an older
+        //! build wrote it.
+        Store::setup(Connection::open(path)?)
+    }
+    pub fn open_in_memory() -> Result<Store, Box<dyn Error>> {
+        //! Open an empty database that lives only as long as the `Store`.
+        Store::setup(Connection::open_in_memory()?)
+    }
+    fn setup(mut conn: Connection) -> Result<Store, Box<dyn Error>> {
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(format!(
+                "database is layout version {}, but this build only reads versions up to {}",
+                version, SCHEMA_VERSION
+            )
+            .into());
+        }
+        if version < SCHEMA_VERSION {
+            let tx = conn.transaction()?;
+            for step in SCHEMA[version..].iter() {
+                tx.execute_batch(step)?;
+            }
+            tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+            tx.commit()?;
+        }
+        Ok(Store { conn })
+    }
+    // Roster
+    pub fn save_roster(&mut self, ros: &Roster) -> Result<(), Box<dyn Error>> {
+        //! Replace the stored roster, employees and pairing rules both.
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM employees", [])?;
+        tx.execute("DELETE FROM pairings", [])?;
+        for (id, em) in ros.iter() {
+            tx.execute(
+                "INSERT INTO employees (id, format, data) VALUES (?1, ?2, ?3)",
+                params![id, VERSION, serde_json::to_string(em)?],
+            )?;
+        }
+        for pairing in ros.pairings() {
+            tx.execute(
+                "INSERT INTO pairings (format, data) VALUES (?1, ?2)",
+                params![VERSION, serde_json::to_string(pairing)?],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+    pub fn load_roster(&self) -> Result<Roster, Box<dyn Error>> {
+        //! Return the stored roster, which is empty if none has been saved.
+        let mut ros = Roster::new();
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, format, data FROM employees")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (id, format, data) = row?;
+            let em: Employee = decode(format, &data, Some(&id))?;
+            ros.add(em);
+        }
+        let mut stmt = self
+            .conn
+            .prepare("SELECT format, data FROM pairings ORDER BY rowid")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (format, data) = row?;
+            let pairing: Pairing = decode(format, &data, None)?;
+            ros.add_pairing(pairing);
+        }
+        Ok(ros)
+    }
+    // Setup
+    pub fn save_setup(&mut self, sched: &Schedule) -> Result<(), Box<dyn Error>> {
+        //! Replace the stored setup with a schedule's events and configuration: store hours, staffing
+        //! requirements, pay, rules and positions. Its shifts are left out; publish a schedule to keep them.
+        let mut config = sched.clone();
+        config.events.clear();
+        config.shifts.iter_mut().for_each(Vec::clear);
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM events", [])?;
+        for ev in sched.events.iter() {
+            tx.execute(
+                "INSERT INTO events (name, day, format, data) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    ev.name,
+                    ev.day.to_index(),
+                    VERSION,
+                    serde_json::to_string(ev)?
+                ],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO setup (id, format, data) VALUES (1, ?1, ?2)",
+            params![VERSION, serde_json::to_string(&config)?],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+    pub fn load_setup(&self) -> Result<Option<Schedule>, Box<dyn Error>> {
+        //! Return a schedule with the stored configuration and events and no shifts, if a setup has been saved.
+        let found: Option<(u32, String)> = self
+            .conn
+            .query_row("SELECT format, data FROM setup WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()?;
+        let mut sched: Schedule = match found {
+            Some((format, data)) => decode(format, &data, None)?,
+            None => return Ok(None),
+        };
+        let mut stmt = self
+            .conn
+            .prepare("SELECT format, data FROM events ORDER BY day, rowid")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (format, data) = row?;
+            let ev: Event = decode(format, &data, None)?;
+            sched.events.push(ev);
+        }
+        Ok(Some(sched))
+    }
+    // History
+    pub fn publish(&mut self, sched: &Schedule) -> Result<(), Box<dyn Error>> {
+        //! Keep a published schedule in the history, replacing any schedule already kept for its week.
+        let week = match sched.week() {
+            Some(week) => week,
+            None => {
+                return Err(
+                    "only schedules anchored to a week with Schedule::set_week can be kept".into(),
+                )
+            }
+        };
+        let published = match sched.published() {
+            Some(date) => date,
+            None => {
+                return Err("only published schedules can be kept; see Schedule::publish".into())
+            }
+        };
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM schedules WHERE week = ?1",
+            params![date_text(week)],
+        )?;
+        tx.execute(
+            "INSERT INTO schedules (week, published, format, data) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                date_text(week),
+                date_text(published),
+                VERSION,
+                serde_json::to_string(sched)?
+            ],
+        )?;
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            let date = sched.date_of(&day).unwrap();
+            for sh in sched.shifts(&day) {
+                tx.execute(
+                    "INSERT INTO shifts (week, date, day, emp_id, start, end, event, position)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        date_text(week),
+                        date_text(date),
+                        i,
+                        sh.emp_id,
+                        sh.start.get_qi(),
+                        sh.end.get_qi(),
+                        sh.event,
+                        sh.position
+                    ],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+    pub fn week(&self, date: NaiveDate) -> Result<Option<Schedule>, Box<dyn Error>> {
+        //! Return the schedule kept for the week containing `date`, if one was published.
+        let found: Option<(u32, String)> = self
+            .conn
+            .query_row(
+                "SELECT format, data FROM schedules WHERE week = ?1",
+                params![date_text(week_of(date))],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        match found {
+            Some((format, data)) => Ok(Some(decode(format, &data, None)?)),
+            None => Ok(None),
+        }
+    }
+    pub fn weeks(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<NaiveDate>, Box<dyn Error>> {
+        //! Return the first day of every kept week that overlaps `from` through `to`, oldest first.
+        let mut stmt = self
+            .conn
+            .prepare("SELECT week FROM schedules WHERE week BETWEEN ?1 AND ?2 ORDER BY week")?;
+        let rows = stmt.query_map(params![date_text(week_of(from)), date_text(to)], |row| {
+            row.get::<_, String>(0)
+        })?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(NaiveDate::parse_from_str(&row?, DATE)?);
+        }
+        Ok(out)
+    }
+    pub fn days_worked(
+        &self,
+        emp_id: &str,
+        day: Option<&Day>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<usize, Box<dyn Error>> {
+        //! Count the dates from `from` through `to` on which an employee had a published shift, only counting
+        //! one day of the week if `day` is given.
+        let count: usize = self.conn.query_row(
+            "SELECT COUNT(DISTINCT date) FROM shifts
+             WHERE emp_id = ?1 AND date BETWEEN ?2 AND ?3 AND (?4 IS NULL OR day = ?4)",
+            params![
+                emp_id,
+                date_text(from),
+                date_text(to),
+                day.map(|d| d.to_index())
+            ],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+    pub fn hours_worked(
+        &self,
+        emp_id: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<f64, Box<dyn Error>> {
+        //! Total the hours of an employee's published shifts from `from` through `to`.
+        let quarters: i64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(end - start), 0) FROM shifts
+             WHERE emp_id = ?1 AND date BETWEEN ?2 AND ?3",
+            params![emp_id, date_text(from), date_text(to)],
+            |row| row.get(0),
+        )?;
+        Ok(quarters as f64 / 4.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Time;
+    fn date(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2019, m, d).unwrap()
+    }
+    #[test]
+    fn setup_round_trip() {
+        let mut store = Store::open_in_memory().unwrap();
+        assert!(store.load_setup().unwrap().is_none());
+        let mut ros = Roster::new();
+        ros.add(Employee::new("Bob".to_string()));
+        ros.add(Employee::new("Kim".to_string()));
+        ros.add_pairing(Pairing::Apart("Bob".to_string(), "Kim".to_string()));
+        store.save_roster(&ros).unwrap();
+        assert_eq!(store.load_roster().unwrap(), ros);
+        let mut sched = Schedule::new();
+        sched.set_hours(Day::Saturday, 9, 21);
+        sched.add_event(
+            "League",
+            "Pokemon",
+            Day::Sunday,
+            Time::from_hour(12),
+            Time::from_hour(15),
+        );
+        sched.assign_shift(
+            "Bob".to_string(),
+            Day::Saturday,
+            Time::from_hour(9),
+            Time::from_hour(17),
+        );
+        store.save_setup(&sched).unwrap();
+        let setup = store.load_setup().unwrap().unwrap();
+        assert_eq!(setup.events, sched.events);
+        assert_eq!(
+            setup.get_req(&Day::Saturday, 40),
+            sched.get_req(&Day::Saturday, 40)
+        );
+        assert!(setup.shifts(&Day::Saturday).is_empty());
+        // An employee written by an older build is upgraded as it loads.
+        let old = serde_json::json!({
+            "iden": "Matt", "avail": [
+                [{ "start": "0:00", "end": "23:45" }], [], [], [], [], [], []
+            ], "hrs": [38, 40],
+            "dob": null, "rate": 15.0, "abils": {}, "roles": [], "time_off": [], "prefs": [], "training": []
+        });
+        store
+            .conn
+            .execute(
+                "INSERT INTO employees (id, format, data) VALUES ('Matt', 1, ?1)",
+                params![old.to_string()],
+            )
+            .unwrap();
+        let ros = store.load_roster().unwrap();
+        let matt = ros.get("Matt").unwrap();
+        let (nine, five) = (Time::from_hour(9), Time::from_hour(17));
+        assert!(matt.is_available_for(&Day::Saturday, &nine, &five));
+        assert!(!matt.is_available_for(&Day::Monday, &nine, &five));
+    }
+    #[test]
+    fn history() {
+        let mut store = Store::open_in_memory().unwrap();
+        let mut sched = Schedule::new();
+        assert!(store.publish(&sched).is_err());
+        sched.set_week(date(6, 8));
+        assert!(store.publish(&sched).is_err());
+        sched.publish(date(6, 1));
+        for day in [Day::Saturday, Day::Sunday].iter() {
+            sched.assign_shift(
+                "Bob".to_string(),
+                day.clone(),
+                Time::from_hour(9),
+                Time::from_hour(17),
+            );
+        }
+        store.publish(&sched).unwrap();
+        // The next week, republished with a change.
+        sched.set_week(date(6, 15));
+        store.publish(&sched).unwrap();
+        sched.shifts[0].clear();
+        store.publish(&sched).unwrap();
+        let (first, last) = quarter(date(6, 8));
+        assert_eq!(
+            store.weeks(first, last).unwrap(),
+            vec![date(6, 8), date(6, 15)]
+        );
+        assert_eq!(
+            store
+                .days_worked("Bob", Some(&Day::Saturday), first, last)
+                .unwrap(),
+            1
+        );
+        assert_eq!(store.days_worked("Bob", None, first, last).unwrap(), 3);
+        assert_eq!(
+            store.hours_worked("Bob", date(6, 9), date(6, 16)).unwrap(),
+            16.0
+        );
+        assert_eq!(store.days_worked("Kim", None, first, last).unwrap(), 0);
+        let kept = store.week(date(6, 12)).unwrap().unwrap();
+        assert_eq!(kept.week(), Some(date(6, 8)));
+        assert_eq!(kept.shifts(&Day::Saturday).len(), 1);
+        assert!(store.week(date(7, 1)).unwrap().is_none());
+    }
+}
@@ -1,13 +1,20 @@
 //! The time module contains generic scheduling and shift information.
 
+use super::cost::Wages;
 use super::emp;
+use super::law::RulePack;
+use super::station::Position;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
 use rand;
+#[cfg(feature = "project")]
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 // ==============================================
 
 /// A day of the week.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
 pub enum Day {
     Saturday,
     Sunday,
@@ -31,6 +38,7 @@ impl Day {
             _ => None,
         }
     }
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Day> {
         match s {
             "Saturday" => Some(Day::Saturday),
@@ -43,8 +51,24 @@ impl Day {
             _ => None,
         }
     }
+    pub fn from_weekday(w: Weekday) -> Day {
+        //! Convert a chrono Weekday into a Day.
+        match w {
+            Weekday::Sat => Day::Saturday,
+            Weekday::Sun => Day::Sunday,
+            Weekday::Mon => Day::Monday,
+            Weekday::Tue => Day::Tuesday,
+            Weekday::Wed => Day::Wednesday,
+            Weekday::Thu => Day::Thursday,
+            Weekday::Fri => Day::Friday,
+        }
+    }
+    pub fn of(date: NaiveDate) -> Day {
+        //! Return the day of the week a date falls on.
+        Day::from_weekday(date.weekday())
+    }
     pub fn to_index(&self) -> usize {
-        let index = match self {
+        match self {
             Day::Saturday => 0,
             Day::Sunday => 1,
             Day::Monday => 2,
@@ -52,8 +76,7 @@ impl Day {
             Day::Wednesday => 4,
             Day::Thursday => 5,
             Day::Friday => 6,
-        };
-        index
+        }
     }
 }
 
@@ -75,7 +98,8 @@ impl fmt::Display for Day {
 // ==============================================
 
 /// An event or class run by the store.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
 pub struct Event {
     pub name: String,
     pub req_emp_ids: Vec<String>,
@@ -133,6 +157,13 @@ impl Event {
         //! Return IDs of all employees required to work this event.
         &self.req_emp_ids
     }
+    pub fn span(&self) -> (usize, usize) {
+        //! Return the quarter-hours this event ties staff up for, from the start of setup to the end of breakdown.
+        (
+            self.start.get_qi() - self.setup.get_qi(),
+            self.end.get_qi() + self.breakdown.get_qi(),
+        )
+    }
     pub fn has_reqs(&self) -> bool {
         //! Check if this event has employee requirements.
         !self.req_emp_ids.is_empty()
@@ -158,16 +189,34 @@ impl fmt::Display for Event {
 
 // ==============================================
 
-/// An employee's shift at the store.
-#[derive(Debug)]
-struct Shift {
+/// An employee's shift at the store, naming the event it works if it was assigned for one.
+///
+/// A shift may be posted to a position for its whole length, with `segments` posting parts of it elsewhere.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub struct Shift {
     pub emp_id: String,
     pub start: Time,
     pub end: Time,
+    pub event: Option<String>,
+    pub position: Option<String>,
+    pub segments: Vec<(Window, String)>,
 }
 
 #[allow(dead_code)]
 impl Shift {
+    // Constructor
+    pub fn new(emp_id: String, start: Time, end: Time) -> Shift {
+        //! Create a new shift that is not tied to any event.
+        Shift {
+            emp_id,
+            start,
+            end,
+            event: None,
+            position: None,
+            segments: Vec::new(),
+        }
+    }
     // todo error checking
     pub fn extend(&mut self, forward: bool) {
         if forward {
@@ -187,6 +236,42 @@ impl Shift {
     pub fn len(&self) -> usize {
         self.end.get_qi() - self.start.get_qi()
     }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn position_at(&self, qi: usize) -> Option<&str> {
+        //! Return the position this shift is posted to during the quarter-hour `qi`, if any.
+        if qi < self.start.get_qi() || qi >= self.end.get_qi() {
+            return None;
+        }
+        // Later segments take precedence over earlier ones.
+        match self
+            .segments
+            .iter()
+            .rev()
+            .find(|(w, _)| w.start.get_qi() <= qi && qi < w.end.get_qi())
+        {
+            Some((_, name)) => Some(name),
+            None => self.position.as_deref(),
+        }
+    }
+    pub fn can_extend(
+        &self,
+        forward: bool,
+        em: &emp::Employee,
+        day: &Day,
+        date: Option<NaiveDate>,
+    ) -> bool {
+        //! Check if extending this shift by a quarter-hour would keep it within the employee's availability.
+        let (start, end) = if forward {
+            (self.start.get_qi(), self.end.get_qi() + 1)
+        } else if self.start.get_qi() > 0 {
+            (self.start.get_qi() - 1, self.end.get_qi())
+        } else {
+            return false;
+        };
+        end < 24 * 4 && em.can_work(day, date, &Time::from_qi(start), &Time::from_qi(end))
+    }
 }
 
 impl fmt::Display for Shift {
@@ -195,7 +280,14 @@ impl fmt::Display for Shift {
             f,
             "{} => {} - {}",
             self.emp_id, self.start.string, self.end.string
-        )
+        )?;
+        if let Some(pos) = &self.position {
+            write!(f, " ({})", pos)?;
+        }
+        for (w, pos) in self.segments.iter() {
+            write!(f, " [{} {}]", pos, w)?;
+        }
+        Ok(())
     }
 }
 
@@ -203,6 +295,8 @@ impl fmt::Display for Shift {
 
 /// A time of day.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "project", serde(into = "String", try_from = "String"))]
 pub struct Time {
     string: String,
     qi: usize,
@@ -210,6 +304,7 @@ pub struct Time {
 
 impl Time {
     // Constructors
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(st: &str) -> Time {
         //! Construct a Time from a &str of the format `"HH:MM"` or `"H:MM"`, using 24-hour notation.
         //!
@@ -222,12 +317,51 @@ impl Time {
         //! let u = Time::from_str("22:45");
         //! assert_eq!(u.get_qi(), 91);
         //! ```
-        let qi = Time::string_to_qi(st);
-        let string = Time::qi_to_string(qi);
+        match Time::parse(st) {
+            Some(t) => t,
+            None => panic!("Bad time!"),
+        }
+    }
+    pub fn parse(st: &str) -> Option<Time> {
+        //! Construct a Time from a &str as `Time::from_str` does, returning None rather than panicking if it's
+        //! malformed or out of range. Minutes are rounded down to the quarter-hour. 12-hour times as written by
+        //! `Display`, such as "9:45a" or "11:00pm", are accepted too.
+        //!
+        //! # Examples
+        //! ```
+        //! use makeshift::time::Time as Time;
+        //! assert_eq!(Time::parse("9:40").unwrap().get_qi(), 38);
+        //! assert_eq!(Time::parse("2:30p").unwrap().get_qi(), 58);
+        //! assert_eq!(Time::parse("12:15am").unwrap().get_qi(), 1);
+        //! assert!(Time::parse("25:00").is_none());
+        //! assert!(Time::parse("13:00p").is_none());
+        //! assert!(Time::parse("noon").is_none());
+        //! assert!(Time::parse("2:30pmmm").is_none());
+        //! assert!(Time::parse("9:00m").is_none());
+        //! ```
+        let st = st.trim().to_lowercase();
+        let (clock, pm) = match st.strip_suffix("am").or_else(|| st.strip_suffix('a')) {
+            Some(clock) => (clock, Some(false)),
+            None => match st.strip_suffix("pm").or_else(|| st.strip_suffix('p')) {
+                Some(clock) => (clock, Some(true)),
+                None => (st.as_str(), None),
+            },
+        };
+        let mut qi = Time::string_to_qi(clock.trim())?;
+        if let Some(pm) = pm {
+            if !(4..13 * 4).contains(&qi) {
+                return None;
+            }
+            qi %= 12 * 4;
+            if pm {
+                qi += 12 * 4;
+            }
+        }
         if qi >= 4 * 24 {
-            panic!("Bad time!")
+            return None;
         }
-        Time { string, qi }
+        let string = Time::qi_to_string(qi);
+        Some(Time { string, qi })
     }
     pub fn from_qi(qi: usize) -> Time {
         //! Construct a Time from a QuarterIndex (the 0-indexed position of its 15-minute chunk in the day).
@@ -273,6 +407,7 @@ impl Time {
         //! ```
         self.string.clone()
     }
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
         //! Return a 12-hour (US) string of this time.
         //!
@@ -286,10 +421,10 @@ impl Time {
         if qi < 4 {
             // 12:MMa
             format!("12:{:0>2}a", qi * 15)
-        } else if qi >= 4 && qi < 12 * 4 {
+        } else if qi < 12 * 4 {
             // 1:MMa -> 11:MMa
             format!("{}:{:0>2}a", qi / 4, (qi % 4) * 15)
-        } else if qi >= 12 * 4 && qi < 13 * 4 {
+        } else if qi < 13 * 4 {
             // 12:MMp
             format!("12:{:0>2}p", (qi % 4) * 15)
         } else {
@@ -305,11 +440,17 @@ impl Time {
     pub fn duration_string(qi: usize) -> String {
         Time::qi_to_string(qi)
     }
-    fn string_to_qi(s: &str) -> usize {
-        let v: Vec<&str> = s.split(":").collect();
-        let hours: usize = v[0].parse().unwrap();
-        let minutes: usize = v[1].parse().unwrap();
-        ((hours * 60) + minutes) / 15
+    fn string_to_qi(s: &str) -> Option<usize> {
+        let v: Vec<&str> = s.split(':').collect();
+        if v.len() != 2 {
+            return None;
+        }
+        let hours: usize = v[0].parse().ok()?;
+        let minutes: usize = v[1].parse().ok()?;
+        if minutes >= 60 {
+            return None;
+        }
+        Some(((hours * 60) + minutes) / 15)
     }
     fn qi_to_string(qi: usize) -> String {
         let hours = qi / 4;
@@ -332,10 +473,10 @@ impl fmt::Display for Time {
         if qi < 4 {
             // 12:MMa
             write!(f, "12:{:0>2}a", qi * 15)
-        } else if qi >= 4 && qi < 12 * 4 {
+        } else if qi < 12 * 4 {
             // 1:MMa -> 11:MMa
             write!(f, "{}:{:0>2}a", qi / 4, (qi % 4) * 15)
-        } else if qi >= 12 * 4 && qi < 13 * 4 {
+        } else if qi < 13 * 4 {
             // 12:MMp
             write!(f, "12:{:0>2}p", (qi % 4) * 15)
         } else {
@@ -353,18 +494,146 @@ impl PartialEq for Time {
 
 // ==============================================
 
+/// A span of time within a single day, running from `start` up to `end`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "project", serde(into = "String", try_from = "String"))]
+pub struct Window {
+    pub start: Time,
+    pub end: Time,
+}
+
+impl Window {
+    // Constructors
+    pub fn new(start: Time, end: Time) -> Window {
+        //! Create a new window from `start` to `end`.
+        Window { start, end }
+    }
+    pub fn all_day() -> Window {
+        //! Create a window covering the whole day.
+        //!
+        //! Because a Time can't be midnight at the end of the day, this runs until 23:45, the latest a shift can end.
+        Window::new(Time::from_qi(0), Time::from_qi(24 * 4 - 1))
+    }
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(st: &str) -> Option<Window> {
+        //! Construct a Window from a &str of the format `"HH:MM-HH:MM"`, using 24-hour notation.
+        //!
+        //! # Examples
+        //! ```
+        //! use makeshift::time::{Time, Window};
+        //! let w = Window::from_str("15:00-21:30").unwrap();
+        //! assert_eq!(w.start, Time::from_hour(15));
+        //! assert_eq!(w.end.get_qi(), 86);
+        //! ```
+        let v: Vec<&str> = st.split('-').map(|t| t.trim()).collect();
+        if v.len() != 2 {
+            return None;
+        }
+        let start = Time::parse(v[0])?;
+        let end = Time::parse(v[1])?;
+        if start.get_qi() >= end.get_qi() {
+            return None;
+        }
+        Some(Window::new(start, end))
+    }
+    // Access
+    pub fn len(&self) -> usize {
+        //! Return the length of this window in quarter-hours.
+        self.end.get_qi().saturating_sub(self.start.get_qi())
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn contains(&self, start: &Time, end: &Time) -> bool {
+        //! Check if the span from `start` to `end` lies entirely within this window.
+        self.start.get_qi() <= start.get_qi() && end.get_qi() <= self.end.get_qi()
+    }
+    pub fn overlaps(&self, start: &Time, end: &Time) -> bool {
+        //! Check if the span from `start` to `end` shares any time with this window.
+        self.start.get_qi() < end.get_qi() && start.get_qi() < self.end.get_qi()
+    }
+    pub fn minus(&self, other: &Window) -> Vec<Window> {
+        //! Return what is left of this window once `other` is cut out of it.
+        //!
+        //! # Examples
+        //! ```
+        //! use makeshift::time::Window;
+        //! let w = Window::from_str("9:00-17:00").unwrap();
+        //! let left = w.minus(&Window::from_str("12:00-13:00").unwrap());
+        //! assert_eq!(left, vec![
+        //!     Window::from_str("9:00-12:00").unwrap(),
+        //!     Window::from_str("13:00-17:00").unwrap(),
+        //! ]);
+        //! ```
+        if !self.overlaps(&other.start, &other.end) {
+            return vec![self.clone()];
+        }
+        let mut out = Vec::new();
+        if self.start.get_qi() < other.start.get_qi() {
+            out.push(Window::new(self.start.clone(), other.start.clone()));
+        }
+        if other.end.get_qi() < self.end.get_qi() {
+            out.push(Window::new(other.end.clone(), self.end.clone()));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} - {}", self.start, self.end)
+    }
+}
+
+// ==============================================
+
+/// How many of an employee's soft preferences a schedule gives them.
+#[derive(Clone, Debug)]
+pub struct PreferenceTally {
+    pub emp_id: String,
+    pub met: usize,
+    pub total: usize,
+    pub score: f64,
+    pub max_score: f64,
+}
+
+impl fmt::Display for PreferenceTally {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} => {}/{} preferences ({:.1} of {:.1})",
+            self.emp_id, self.met, self.total, self.score, self.max_score
+        )
+    }
+}
+
+// ==============================================
+
 /// A full week's schedule, including events and shifts.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
 pub struct Schedule {
     pub events: Vec<Event>,
+    #[cfg_attr(feature = "project", serde(with = "crate::project::reqs"))]
     raw_reqs: [[i32; 24 * 4]; 7],
-    shifts: [Vec<Shift>; 7],
+    #[cfg_attr(feature = "project", serde(with = "crate::project::open_hours"))]
+    open_hours: [Option<(usize, usize)>; 7],
+    pub(crate) shifts: [Vec<Shift>; 7],
+    week: Option<NaiveDate>,
+    min_rest: usize,
+    wages: Wages,
+    packs: Vec<RulePack>,
+    published: Option<NaiveDate>,
+    key_role: Option<String>,
+    pub(crate) positions: Vec<Position>,
 }
 
 impl fmt::Debug for Schedule {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut rr = String::new();
         for day_arr in self.raw_reqs.iter() {
-            rr.push_str("[");
+            rr.push('[');
             for (i, cover) in day_arr.iter().enumerate() {
                 rr.push_str(&format!("{}", cover));
                 if i != day_arr.len() - 1 {
@@ -374,8 +643,16 @@ impl fmt::Debug for Schedule {
             rr.push_str("]\n");
         }
         f.debug_struct("Schedule")
+            .field("week", &self.week)
+            .field("min_rest", &self.min_rest)
+            .field("wages", &self.wages)
+            .field("packs", &self.packs)
+            .field("published", &self.published)
+            .field("key_role", &self.key_role)
+            .field("positions", &self.positions)
             .field("events", &self.events)
             .field("shifts", &self.shifts)
+            .field("open_hours", &self.open_hours)
             .field("raw_reqs", &rr)
             .finish()
     }
@@ -391,13 +668,19 @@ impl fmt::Display for Schedule {
                 out.push_str(&format!("\n{}", shift));
             }
             if i != 6 {
-                out.push_str("\n")
+                out.push('\n')
             }
         }
         write!(f, "{}", out)
     }
 }
 
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule::new()
+    }
+}
+
 impl Schedule {
     // Constructor
     pub fn new() -> Schedule {
@@ -405,6 +688,7 @@ impl Schedule {
         Schedule {
             events: Vec::new(),
             raw_reqs: [[0; 24 * 4]; 7],
+            open_hours: [None; 7],
             shifts: [
                 Vec::new(),
                 Vec::new(),
@@ -414,10 +698,17 @@ impl Schedule {
                 Vec::new(),
                 Vec::new(),
             ],
+            week: None,
+            min_rest: 10 * 4,
+            wages: Wages::new(),
+            packs: Vec::new(),
+            published: None,
+            key_role: None,
+            positions: Vec::new(),
         }
     }
     // Display/Access
-    pub fn print_reqs(&self) -> () {
+    pub fn print_reqs(&self) {
         //! Print the quarter-hourly staffing requirements for all time during which the store is open.
         for (i, day) in self.raw_reqs.iter().enumerate() {
             let day_name = match Day::from_index(i) {
@@ -427,12 +718,12 @@ impl Schedule {
             println!("\n{}", day_name);
             for (j, quarter_req) in day.iter().enumerate() {
                 if *quarter_req > 0 {
-                    println!("{} - {}", Time::from_qi(j).to_string(), quarter_req);
+                    println!("{} - {}", Time::from_qi(j), quarter_req);
                 }
             }
         }
     }
-    pub fn print_events(&self) -> () {
+    pub fn print_events(&self) {
         //! Print all events listed on this schedule.
         for event in self.events.iter() {
             event.print();
@@ -441,6 +732,78 @@ impl Schedule {
     pub fn get_events(&self) -> &Vec<Event> {
         &self.events
     }
+    pub fn week(&self) -> Option<NaiveDate> {
+        //! Return the Saturday this schedule's week begins on, if it has been anchored to real dates.
+        self.week
+    }
+    pub fn date_of(&self, day: &Day) -> Option<NaiveDate> {
+        //! Return the calendar date of a day in this schedule's week, if it has been anchored to real dates.
+        self.week
+            .map(|sat| sat + Duration::days(day.to_index() as i64))
+    }
+    pub fn shifts(&self, day: &Day) -> &[Shift] {
+        //! Return every shift assigned on a day.
+        &self.shifts[day.to_index()]
+    }
+    pub fn min_rest(&self) -> usize {
+        //! Return the shortest break, in quarter-hours, allowed between an employee's shifts on consecutive days.
+        self.min_rest
+    }
+    pub fn rule_packs(&self) -> &[RulePack] {
+        //! Return the labor-law rule packs this schedule is held to.
+        &self.packs
+    }
+    pub fn published(&self) -> Option<NaiveDate> {
+        //! Return the date this schedule was published to staff, if it has been.
+        self.published
+    }
+    pub fn key_role(&self) -> Option<&str> {
+        //! Return the role held by staff with the store keys, if opening and closing require one.
+        self.key_role.as_deref()
+    }
+    pub fn wages(&self) -> &Wages {
+        //! Return the pay rules used to price this schedule.
+        &self.wages
+    }
+    pub fn opening(&self, day: &Day) -> Option<usize> {
+        //! Return the first quarter-hour on a day during which any staff are required.
+        self.raw_reqs[day.to_index()].iter().position(|r| *r > 0)
+    }
+    pub fn open_hours(&self, day: &Day) -> Option<(usize, usize)> {
+        //! Return the quarter-hours the store opens and closes at on a day, if they were set with `set_hours`.
+        self.open_hours[day.to_index()]
+    }
+    pub fn closing(&self, day: &Day) -> Option<usize> {
+        //! Return the quarter-hour on a day at which staff are no longer required.
+        self.raw_reqs[day.to_index()]
+            .iter()
+            .rposition(|r| *r > 0)
+            .map(|qi| qi + 1)
+    }
+    pub fn timeline(&self, day: &Day) -> Option<(usize, usize)> {
+        //! Return the whole hours a day's timeline should cover, from the earliest of opening, shifts and event
+        //! setup to the latest of closing, shifts and event breakdown, or None if nothing happens that day.
+        let mut spans: Vec<(usize, usize)> = self.shifts[day.to_index()]
+            .iter()
+            .map(|sh| (sh.start.get_qi(), sh.end.get_qi()))
+            .collect();
+        spans.extend(
+            self.events
+                .iter()
+                .filter(|ev| ev.day == *day)
+                .map(|ev| ev.span()),
+        );
+        if let (Some(open), Some(close)) = (self.opening(day), self.closing(day)) {
+            spans.push((open, close));
+        }
+        let lo = spans.iter().map(|s| s.0).min()? / 4 * 4;
+        let hi = spans.iter().map(|s| s.1).max()?.div_ceil(4) * 4;
+        Some((lo, hi.clamp(lo + 4, 96)))
+    }
+    pub fn get_req(&self, day: &Day, qi: usize) -> i32 {
+        //! Return the number of staff required during the quarter-hour `qi` of `day`.
+        self.raw_reqs[day.to_index()][qi]
+    }
     // Modification
     pub fn add_event(
         &mut self,
@@ -458,7 +821,7 @@ impl Schedule {
     }
     pub fn assign_shift(&mut self, emp_id: String, day: Day, start: Time, end: Time) {
         //! Assign a new shift to the employee with id emp_id.
-        let sh = Shift { emp_id, start, end };
+        let sh = Shift::new(emp_id, start, end);
         self.shifts[day.to_index()].push(sh);
     }
     pub fn assign_event(&mut self, emp_id: String, event: Event) {
@@ -470,13 +833,60 @@ impl Schedule {
         let end = event.end.get_qi() + event.breakdown.get_qi();
         let end = Time::from_qi(end);
 
-        let sh = Shift { emp_id, start, end };
+        let sh = Shift {
+            event: Some(event.name.clone()),
+            ..Shift::new(emp_id, start, end)
+        };
         self.shifts[event.day.to_index()].push(sh);
     }
-    pub fn set_hours(&mut self, day: Day, start: usize, end: usize) -> () {
+    pub fn set_week(&mut self, date: NaiveDate) {
+        //! Anchor this schedule to the week (Saturday through Friday) containing `date`.
+        let back = Day::of(date).to_index() as i64;
+        self.week = Some(date - Duration::days(back));
+    }
+    pub fn add_rule_pack(&mut self, pack: RulePack) {
+        //! Hold this schedule to a labor-law rule pack, replacing any selected pack with the same name.
+        self.packs.retain(|p| p.name != pack.name);
+        self.packs.push(pack);
+    }
+    pub fn publish(&mut self, date: NaiveDate) {
+        //! Record the date this schedule was published to staff.
+        self.published = Some(date);
+    }
+    pub fn set_key_role(&mut self, role: Option<&str>) {
+        //! Require someone with `role` to open and close the store each day, or clear the requirement.
+        self.key_role = role.map(|r| r.to_owned());
+    }
+    pub fn wages_mut(&mut self) -> &mut Wages {
+        &mut self.wages
+    }
+    pub fn set_min_rest(&mut self, hours: usize) {
+        //! Set the shortest break, in hours, allowed between an employee's shifts on consecutive days.
+        self.min_rest = hours * 4;
+    }
+    pub(crate) fn absorb_shifts(&mut self, other: &Schedule) {
+        // Take on another schedule's shifts, events and strictest rules, but not its staffing or pay.
+        for i in 0..7 {
+            self.shifts[i].extend(other.shifts[i].iter().cloned());
+        }
+        self.events.extend(other.events.iter().cloned());
+        for pack in other.packs.iter() {
+            if !self.packs.contains(pack) {
+                self.packs.push(pack.clone());
+            }
+        }
+        self.min_rest = self.min_rest.max(other.min_rest);
+        self.week = self.week.or(other.week);
+    }
+    pub fn set_req(&mut self, day: &Day, qi: usize, num_emps: i32) {
+        //! Set the number of staff required during the quarter-hour `qi` of `day`.
+        self.raw_reqs[day.to_index()][qi] = num_emps;
+    }
+    pub fn set_hours(&mut self, day: Day, start: usize, end: usize) {
         //! Set the store's open and close hours for a given day.
         let start = Time::from_hour(start).qi;
         let end = Time::from_hour(end).qi;
+        self.open_hours[day.to_index()] = Some((start, end));
         for qi in start - 1..start {
             self.raw_reqs[day.to_index()][qi] = 3;
         }
@@ -501,118 +911,132 @@ impl Schedule {
             self.assign_event(id, ev);
         }
     }
-    // Validation
-    fn hours_assigned_valid(&self, id: &str, ros: &emp::Roster) -> bool {
-        let mut total = 0;
-        for day in self.shifts.iter() {
-            for shift in day.iter() {
-                if shift.emp_id == id {
-                    total += shift.end.get_qi() - shift.start.get_qi();
-                }
-            }
-        }
-        let em = ros.get(id.to_string());
-        let min = em.get_hours().min() * 4;
-        let max = em.get_hours().max() * 4;
-        if min <= total && total <= max {
-            true
-        } else {
-            println!(">>> {} - {}", id, Time::duration_string(total));
-            false
-        }
-    }
-    fn all_shifts_okay_length(&self, _ros: &emp::Roster) -> bool {
-        true
-    }
-    fn coverage(&self, day: Day) -> [i32; 96] {
+    // Coverage
+    pub fn coverage(&self, day: Day) -> [i32; 96] {
+        //! Return the staff on hand during each quarter-hour of a day, less those tied up running events.
         let mut out = [0; 96];
         for shift in self.shifts[day.to_index()].iter() {
             let s = shift.start.get_qi();
             let e = shift.end.get_qi();
-            for index in s..e {
-                out[index] += 1;
+            for cover in out[s..e].iter_mut() {
+                *cover += 1;
             }
         }
         for event in self.events.iter() {
             if day == event.day {
                 let s = event.start.get_qi() - event.setup.get_qi();
                 let e = event.end.get_qi() + event.breakdown.get_qi();
-                for index in s..e {
-                    out[index] -= event.num_emps;
+                for cover in out[s..e].iter_mut() {
+                    *cover -= event.num_emps;
                 }
             }
         }
         out
     }
-    fn adequate_coverage(&self) -> bool {
-        let mut adequate = true;
-        for i in 0..7 {
-            println!("Checking coverage for {}", Day::from_index(i).unwrap());
-            let len = self.raw_reqs[i].len();
-            let coverage = self.coverage(Day::from_index(i).unwrap());
-            for j in 0..len {
-                if coverage[j] < self.raw_reqs[i][j] {
-                    adequate = false;
-                    println!(
-                        "Low coverage at {} on {}: {}",
-                        Time::from_qi(j),
-                        Day::from_index(i).unwrap(),
-                        coverage[j]
-                    );
+    // Scoring
+    pub(crate) fn shifts_of(&self, id: &str) -> Vec<(Day, &Shift)> {
+        let mut out = Vec::new();
+        for (i, day) in self.shifts.iter().enumerate() {
+            for shift in day.iter() {
+                if shift.emp_id == id {
+                    out.push((Day::from_index(i).unwrap(), shift));
                 }
             }
         }
-        adequate
+        out
     }
-    pub fn is_valid(&self, ros: &emp::Roster) -> bool {
-        let mut valid = true;
-        for (id, _emp) in ros.iter() {
-            if !self.hours_assigned_valid(id, ros) {
-                println!("{} has invalid # of hours", id);
-                valid = false;
+    pub fn preference_met(&self, id: &str, pref: &emp::Preference) -> bool {
+        //! Check if this schedule gives an employee one of their soft preferences.
+        let shifts = self.shifts_of(id);
+        match pref {
+            emp::Preference::Day(d) => shifts.iter().any(|(day, _)| day == d),
+            emp::Preference::Times(w) => {
+                !shifts.is_empty() && shifts.iter().all(|(_, sh)| w.contains(&sh.start, &sh.end))
+            }
+            emp::Preference::Opening => shifts.iter().any(|(day, sh)| match self.opening(day) {
+                Some(qi) => sh.start.get_qi() <= qi && qi < sh.end.get_qi(),
+                None => false,
+            }),
+            emp::Preference::Closing => shifts.iter().any(|(day, sh)| match self.closing(day) {
+                Some(qi) => sh.start.get_qi() < qi && qi <= sh.end.get_qi(),
+                None => false,
+            }),
+            emp::Preference::ShiftLength(min, max) => {
+                !shifts.is_empty()
+                    && shifts
+                        .iter()
+                        .all(|(_, sh)| (min * 4..=max * 4).contains(&sh.len()))
+            }
+            emp::Preference::MaxDays(n) => {
+                let mut days: Vec<usize> = shifts.iter().map(|(d, _)| d.to_index()).collect();
+                days.dedup();
+                days.len() <= *n
             }
         }
-        if !self.all_shifts_okay_length(ros) {
-            println!("Shift length issue - schedule invalid");
-            valid = false;
-        }
-        if !self.adequate_coverage() {
-            println!("Not enough coverage - schedule invalid");
-            valid = false;
+    }
+    pub fn preference_report(&self, ros: &emp::Roster) -> Vec<PreferenceTally> {
+        //! Tally how many of each employee's soft preferences this schedule gives them.
+        let mut out = Vec::new();
+        for (id, em) in ros.iter() {
+            let mut tally = PreferenceTally {
+                emp_id: id.clone(),
+                met: 0,
+                total: 0,
+                score: 0.0,
+                max_score: 0.0,
+            };
+            for (pref, weight) in em.preferences() {
+                tally.total += 1;
+                tally.max_score += weight;
+                if self.preference_met(id, pref) {
+                    tally.met += 1;
+                    tally.score += weight;
+                }
+            }
+            out.push(tally);
         }
-        valid
+        out.sort_by(|a, b| a.emp_id.cmp(&b.emp_id));
+        out
+    }
+    pub fn score(&self, ros: &emp::Roster) -> f64 {
+        //! Score this schedule by the total weight of the soft preferences it gives employees. Higher is better.
+        self.preference_report(ros).iter().map(|t| t.score).sum()
     }
     // Generation
-    pub fn expand_shifts(&mut self, emp_id: String) {
+    pub fn expand_shifts(&mut self, emp_id: String, ros: &emp::Roster) {
+        //! Lengthen each of an employee's shifts towards 8 hours, without leaving open hours or their availability.
+        let em = match ros.get(&emp_id) {
+            Some(em) => em,
+            None => {
+                println!(
+                    "Can't expand shifts for {}, who isn't on the roster",
+                    emp_id
+                );
+                return;
+            }
+        };
         for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            let date = self.date_of(&day);
             for shift in &mut self.shifts[i] {
                 if shift.emp_id != emp_id {
                     continue;
                 }
                 let direc: bool = rand::random();
-                while shift.len() < 8 * 4
-                    && self.raw_reqs[i][shift.end.get_qi() + 1] != 0
-                    && self.raw_reqs[i][shift.start.get_qi() - 1] != 0
-                {
-                    println!("Extending {}", (if direc { "forward" } else { "backward" }));
-                    shift.extend(direc);
-                }
-                while shift.len() < 8 * 4
-                    && self.raw_reqs[i][shift.end.get_qi() + 1] != 0
-                    && self.raw_reqs[i][shift.start.get_qi() - 1] != 0
-                {
-                    println!(
-                        "Extending {}",
-                        (if !direc { "forward" } else { "backward" })
-                    );
-                    shift.extend(!direc);
+                for forward in [direc, !direc].iter() {
+                    while shift.len() < 8 * 4
+                        && self.raw_reqs[i][shift.end.get_qi() + 1] != 0
+                        && self.raw_reqs[i][shift.start.get_qi() - 1] != 0
+                        && shift.can_extend(*forward, em, &day, date)
+                    {
+                        println!(
+                            "Extending {}",
+                            (if *forward { "forward" } else { "backward" })
+                        );
+                        shift.extend(*forward);
+                    }
                 }
-                println!(
-                    "New shift: {} {} -> {}",
-                    Day::from_index(i).unwrap(),
-                    shift.start.to_string(),
-                    shift.end.to_string()
-                );
+                println!("New shift: {} {} -> {}", day, shift.start, shift.end);
             }
         }
     }
@@ -621,6 +1045,7 @@ impl Schedule {
 #[allow(dead_code)]
 #[cfg(test)]
 mod tests {
+    use super::super::valid::Problem;
     use super::*;
     #[test]
     fn midnight() {
@@ -714,4 +1139,117 @@ mod tests {
         let ev = ev_setup();
         assert!(ev.has_reqs())
     }
+    #[test]
+    fn window_contains() {
+        let w = Window::from_str("15:00-21:00").unwrap();
+        assert!(w.contains(&Time::from_hour(15), &Time::from_hour(21)));
+        assert!(!w.contains(&Time::from_hour(14), &Time::from_hour(18)));
+        assert!(w.overlaps(&Time::from_hour(14), &Time::from_hour(18)));
+        assert!(!w.overlaps(&Time::from_hour(21), &Time::from_hour(22)));
+        assert!(Window::from_str("21:00-15:00").is_none());
+    }
+    #[test]
+    fn availability_windows() {
+        let mut ros = emp::Roster::new();
+        let mut em = emp::Employee::new("Matt".to_string());
+        em.set_windows(Day::Tuesday, vec![Window::from_str("15:00-23:45").unwrap()]);
+        ros.add(em);
+        let mut sched = Schedule::new();
+        sched.assign_shift(
+            "Matt".to_string(),
+            Day::Tuesday,
+            Time::from_hour(15),
+            Time::from_hour(22),
+        );
+        assert!(!sched
+            .problems(&ros)
+            .iter()
+            .any(|p| matches!(p, Problem::Unavailable { .. })));
+        sched.assign_shift(
+            "Matt".to_string(),
+            Day::Tuesday,
+            Time::from_hour(10),
+            Time::from_hour(14),
+        );
+        assert!(sched
+            .problems(&ros)
+            .iter()
+            .any(|p| matches!(p, Problem::Unavailable { .. })));
+    }
+    #[test]
+    fn week_dates() {
+        let mut sched = Schedule::new();
+        sched.set_week(NaiveDate::from_ymd_opt(2019, 6, 12).unwrap());
+        assert_eq!(sched.week(), NaiveDate::from_ymd_opt(2019, 6, 8));
+        assert_eq!(
+            sched.date_of(&Day::Friday),
+            NaiveDate::from_ymd_opt(2019, 6, 14)
+        );
+    }
+    #[test]
+    fn approved_time_off() {
+        let mut ros = emp::Roster::new();
+        let mut em = emp::Employee::new("Matt".to_string());
+        let day = NaiveDate::from_ymd_opt(2019, 6, 11).unwrap();
+        let i = em.request_time_off(emp::TimeOff::partial(
+            day,
+            Window::from_str("9:00-13:00").unwrap(),
+        ));
+        ros.add(em);
+        let mut sched = Schedule::new();
+        sched.set_week(day);
+        sched.assign_shift(
+            "Matt".to_string(),
+            Day::Tuesday,
+            Time::from_hour(10),
+            Time::from_hour(16),
+        );
+        let on_leave = |sched: &Schedule, ros: &emp::Roster| {
+            sched
+                .problems(ros)
+                .iter()
+                .any(|p| matches!(p, Problem::TimeOff { .. }))
+        };
+        assert!(!on_leave(&sched, &ros));
+        ros.get_mut("Matt")
+            .unwrap()
+            .time_off_mut(i)
+            .unwrap()
+            .approve();
+        assert!(on_leave(&sched, &ros));
+        let em = ros.get("Matt").unwrap();
+        assert!(em.can_work(
+            &Day::Tuesday,
+            Some(day),
+            &Time::from_hour(13),
+            &Time::from_hour(18)
+        ));
+        assert!(em.can_work(
+            &Day::Tuesday,
+            None,
+            &Time::from_hour(10),
+            &Time::from_hour(16)
+        ));
+    }
+    #[test]
+    fn preferences() {
+        let mut ros = emp::Roster::new();
+        let mut em = emp::Employee::new("Matt".to_string());
+        em.add_preference(emp::Preference::Opening, 2.0);
+        em.add_preference(emp::Preference::Day(Day::Sunday), 1.0);
+        em.add_preference(emp::Preference::ShiftLength(6, 8), 1.5);
+        ros.add(em);
+        let mut sched = Schedule::new();
+        sched.set_hours(Day::Saturday, 10, 18);
+        sched.assign_shift(
+            "Matt".to_string(),
+            Day::Saturday,
+            Time::from_qi(39),
+            Time::from_hour(17),
+        );
+        let report = sched.preference_report(&ros);
+        assert_eq!(report[0].met, 2);
+        assert_eq!(report[0].total, 3);
+        assert_eq!(sched.score(&ros), 3.5);
+    }
 }
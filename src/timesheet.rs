@@ -0,0 +1,370 @@
+//! The timesheet module totals scheduled hours for payroll and writes them out as CSV in a configurable layout.
+use super::emp::Roster;
+use super::time::{Day, Schedule};
+use chrono::format::{Item, StrftimeItems};
+use chrono::NaiveDate;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+// ==============================================
+
+/// Scheduled hours for one employee over one day, or over the whole week if `day` is None.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimesheetRow {
+    pub emp_id: String,
+    pub day: Option<Day>,
+    pub date: Option<NaiveDate>,
+    pub scheduled: f64,
+    pub unpaid_break: f64,
+    pub regular: f64,
+    pub overtime: f64,
+    pub rate: f64,
+}
+
+impl TimesheetRow {
+    fn new(emp_id: &str, day: Option<Day>, date: Option<NaiveDate>, rate: f64) -> TimesheetRow {
+        TimesheetRow {
+            emp_id: emp_id.to_owned(),
+            day,
+            date,
+            scheduled: 0.0,
+            unpaid_break: 0.0,
+            regular: 0.0,
+            overtime: 0.0,
+            rate,
+        }
+    }
+    // Access
+    pub fn paid(&self) -> f64 {
+        //! Return the hours to be paid, regular and overtime together.
+        self.regular + self.overtime
+    }
+}
+
+// ==============================================
+
+/// A value that can fill a timesheet column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    EmployeeId,
+    Day,
+    Date,
+    Scheduled,
+    Break,
+    Regular,
+    Overtime,
+    Paid,
+    Rate,
+}
+
+impl Field {
+    // Constructor
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Field> {
+        //! Parse a field by the name used in column mapping files.
+        //!
+        //! # Examples
+        //! ```
+        //! use makeshift::timesheet::Field;
+        //! assert_eq!(Field::from_str("overtime"), Some(Field::Overtime));
+        //! assert_eq!(Field::from_str("Break"), Some(Field::Break));
+        //! assert_eq!(Field::from_str("tips"), None);
+        //! ```
+        match s.trim().to_lowercase().as_str() {
+            "id" => Some(Field::EmployeeId),
+            "day" => Some(Field::Day),
+            "date" => Some(Field::Date),
+            "scheduled" => Some(Field::Scheduled),
+            "break" => Some(Field::Break),
+            "regular" => Some(Field::Regular),
+            "overtime" => Some(Field::Overtime),
+            "paid" => Some(Field::Paid),
+            "rate" => Some(Field::Rate),
+            _ => None,
+        }
+    }
+}
+
+// ==============================================
+
+/// Which columns a timesheet has, in order, with their headers and what fills them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimesheetFormat {
+    columns: Vec<(String, Field)>,
+    date_format: String,
+    weekly: bool,
+    daily: bool,
+}
+
+impl Default for TimesheetFormat {
+    fn default() -> Self {
+        //! Every field under its own name, with daily rows followed by a weekly total for each employee.
+        let mut format = TimesheetFormat::new();
+        for (header, field) in [
+            ("id", Field::EmployeeId),
+            ("day", Field::Day),
+            ("date", Field::Date),
+            ("scheduled", Field::Scheduled),
+            ("break", Field::Break),
+            ("regular", Field::Regular),
+            ("overtime", Field::Overtime),
+            ("paid", Field::Paid),
+        ]
+        .iter()
+        {
+            format.column(header, *field);
+        }
+        format
+    }
+}
+
+impl TimesheetFormat {
+    // Constructors
+    pub fn new() -> TimesheetFormat {
+        //! Create a format with no columns, ISO dates, and both daily and weekly rows.
+        TimesheetFormat {
+            columns: Vec::new(),
+            date_format: "%Y-%m-%d".to_string(),
+            weekly: true,
+            daily: true,
+        }
+    }
+    pub fn from_reader<R: Read>(reader: R) -> Result<TimesheetFormat, Box<dyn Error>> {
+        //! Read a column mapping from CSV with `header` and `field` columns, one row per timesheet column in
+        //! order. Rows with a `header` of `@date_format`, `@daily` or `@weekly` set those options instead.
+        let mut rdr = csv::Reader::from_reader(reader);
+        let heads = rdr.headers()?.clone();
+        let find = |name: &str| {
+            heads
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name))
+        };
+        let (h, f) = match (find("header"), find("field")) {
+            (Some(h), Some(f)) => (h, f),
+            _ => return Err("column mappings need \"header\" and \"field\" columns".into()),
+        };
+        let mut out = TimesheetFormat::new();
+        for (i, result) in rdr.records().enumerate() {
+            let sr = result?;
+            let (header, value) = (sr.get(h).unwrap_or(""), sr.get(f).unwrap_or("").trim());
+            let flag = || match value.to_lowercase().as_str() {
+                "true" | "yes" => Ok(true),
+                "false" | "no" => Ok(false),
+                _ => Err(format!("line {}: \"{}\" isn't yes or no", i + 2, value)),
+            };
+            match header {
+                "@date_format" => {
+                    out.set_date_format(value)
+                        .map_err(|e| format!("line {}: {}", i + 2, e))?;
+                }
+                "@daily" => out.daily = flag()?,
+                "@weekly" => out.weekly = flag()?,
+                _ => match Field::from_str(value) {
+                    Some(field) => {
+                        out.column(header, field);
+                    }
+                    None => {
+                        return Err(format!("line {}: unknown field \"{}\"", i + 2, value).into());
+                    }
+                },
+            }
+        }
+        if out.columns.is_empty() {
+            return Err("column mappings name no columns".into());
+        }
+        Ok(out)
+    }
+    pub fn load(path: &Path) -> Result<TimesheetFormat, Box<dyn Error>> {
+        TimesheetFormat::from_reader(File::open(path)?)
+    }
+    // Modification
+    pub fn column(&mut self, header: &str, field: Field) -> &mut TimesheetFormat {
+        //! Add a column at the end.
+        self.columns.push((header.to_owned(), field));
+        self
+    }
+    pub fn set_date_format(
+        &mut self,
+        format: &str,
+    ) -> Result<&mut TimesheetFormat, Box<dyn Error>> {
+        //! Set the chrono format string used for dates, e.g. "%m/%d/%Y". Fails, leaving the format as it was, if
+        //! chrono can't read the string or it asks for more than a date, such as the hour.
+        let sample = NaiveDate::from_ymd_opt(2019, 6, 8).unwrap();
+        let mut probe = String::new();
+        if StrftimeItems::new(format).any(|item| item == Item::Error)
+            || write!(probe, "{}", sample.format(format)).is_err()
+        {
+            return Err(format!("\"{}\" isn't a date format", format).into());
+        }
+        self.date_format = format.to_owned();
+        Ok(self)
+    }
+    pub fn set_rows(&mut self, daily: bool, weekly: bool) -> &mut TimesheetFormat {
+        //! Choose whether to write a row per employee per day worked, a weekly total per employee, or both.
+        self.daily = daily;
+        self.weekly = weekly;
+        self
+    }
+    // Export
+    fn cell(&self, row: &TimesheetRow, field: Field) -> String {
+        let hours = |h: f64| format!("{:.2}", h);
+        match field {
+            Field::EmployeeId => row.emp_id.clone(),
+            Field::Day => row
+                .day
+                .as_ref()
+                .map_or("Week".to_string(), |d| d.to_string()),
+            Field::Date => row
+                .date
+                .map_or(String::new(), |d| d.format(&self.date_format).to_string()),
+            Field::Scheduled => hours(row.scheduled),
+            Field::Break => hours(row.unpaid_break),
+            Field::Regular => hours(row.regular),
+            Field::Overtime => hours(row.overtime),
+            Field::Paid => hours(row.paid()),
+            Field::Rate => format!("{:.2}", row.rate),
+        }
+    }
+    pub fn write<W: Write>(&self, rows: &[TimesheetRow], writer: W) -> Result<(), Box<dyn Error>> {
+        //! Write timesheet rows as CSV in this format.
+        let mut wtr = csv::Writer::from_writer(writer);
+        wtr.write_record(self.columns.iter().map(|(header, _)| header))?;
+        for row in rows {
+            if (row.day.is_some() && !self.daily) || (row.day.is_none() && !self.weekly) {
+                continue;
+            }
+            wtr.write_record(self.columns.iter().map(|(_, field)| self.cell(row, *field)))?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+// ==============================================
+
+impl Schedule {
+    pub fn timesheet(&self, ros: &Roster) -> Vec<TimesheetRow> {
+        //! Total each employee's scheduled hours for every day they work, followed by their weekly total, with
+        //! unpaid breaks deducted and paid time split into regular and overtime.
+        let mut ids: Vec<String> = Vec::new();
+        for i in 0..7 {
+            for sh in self.shifts(&Day::from_index(i).unwrap()) {
+                if !ids.contains(&sh.emp_id) {
+                    ids.push(sh.emp_id.clone());
+                }
+            }
+        }
+        ids.sort();
+        let mut out = Vec::new();
+        for id in ids {
+            let rate = self.wages().rate_of(&id, ros);
+            let mut week = TimesheetRow::new(&id, None, None, rate);
+            let mut days: Vec<TimesheetRow> = Vec::new();
+            for (day, sh, paid) in self.paid_time(&id) {
+                if days.last().is_none_or(|row| row.day.as_ref() != Some(&day)) {
+                    let date = self.date_of(&day);
+                    days.push(TimesheetRow::new(&id, Some(day), date, rate));
+                }
+                for row in [days.last_mut().unwrap(), &mut week] {
+                    row.scheduled += sh.len() as f64 / 4.0;
+                    row.unpaid_break += paid.unpaid as f64 / 4.0;
+                    row.regular += paid.regular as f64 / 4.0;
+                    row.overtime += paid.overtime as f64 / 4.0;
+                }
+            }
+            out.extend(days);
+            out.push(week);
+        }
+        out
+    }
+    pub fn write_timesheet(
+        &self,
+        path: &Path,
+        ros: &Roster,
+        format: &TimesheetFormat,
+    ) -> Result<(), Box<dyn Error>> {
+        //! Write the week's timesheet to a CSV file in the given format.
+        format.write(&self.timesheet(ros), File::create(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::law::RulePack;
+    use crate::time::Time;
+    fn setup() -> (Schedule, Roster) {
+        let (mut sched, ros) = Fixture::new()
+            .employee("Matt", |em| em.set_rate(Some(12.0)))
+            .dated()
+            .shift("Matt", Day::Saturday, "9:00", "17:00")
+            .shift("Matt", Day::Sunday, "9:00", "17:00")
+            .shift("Matt", Day::Monday, "9:00", "17:00")
+            .build();
+        sched.wages_mut().set_overtime(16, 1.5);
+        sched.add_rule_pack(RulePack::builtin("meal-breaks").unwrap());
+        (sched, ros)
+    }
+    #[test]
+    fn hours_split() {
+        let (sched, ros) = setup();
+        let rows = sched.timesheet(&ros);
+        assert_eq!(rows.len(), 4);
+        // 8 scheduled less a half-hour break, and overtime past 16 paid hours.
+        assert_eq!(rows[0].regular, 7.5);
+        assert_eq!(rows[0].unpaid_break, 0.5);
+        assert_eq!(rows[2].regular, 1.0);
+        assert_eq!(rows[2].overtime, 6.5);
+        let week = &rows[3];
+        assert_eq!(week.day, None);
+        assert_eq!(week.scheduled, 24.0);
+        assert_eq!((week.regular, week.overtime), (16.0, 6.5));
+        assert_eq!(sched.cost(&ros).total, 12.0 * (16.0 + 6.5 * 1.5));
+    }
+    #[test]
+    fn column_mappings() {
+        let (sched, ros) = setup();
+        let spec = "header,field\n\
+                    Employee Number,id\n\
+                    Work Date,date\n\
+                    REG,regular\n\
+                    OT,overtime\n\
+                    Pay Rate,rate\n\
+                    @date_format,%m/%d/%Y\n\
+                    @weekly,no\n";
+        let format = TimesheetFormat::from_reader(spec.as_bytes()).unwrap();
+        let mut out = Vec::new();
+        format.write(&sched.timesheet(&ros), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "Employee Number,Work Date,REG,OT,Pay Rate");
+        assert_eq!(lines[1], "Matt,06/08/2019,7.50,0.00,12.00");
+        assert_eq!(lines.len(), 4);
+        // Someone off the roster is paid, and priced, at the default rate.
+        let (mut sched, ros) = setup();
+        sched.wages_mut().set_default_rate(9.0);
+        sched.assign_shift(
+            "Ghost".to_string(),
+            Day::Friday,
+            Time::from_hour(9),
+            Time::from_hour(13),
+        );
+        let rows = sched.timesheet(&ros);
+        let ghost = rows.iter().find(|row| row.emp_id == "Ghost").unwrap();
+        assert_eq!(ghost.rate, 9.0);
+        assert_eq!(
+            sched.employee_cost("Ghost", &ros),
+            ghost.rate * ghost.paid()
+        );
+        let bad = "header,field\nTips,tips\n";
+        assert!(TimesheetFormat::from_reader(bad.as_bytes()).is_err());
+        let bad = "header,field\nid,id\n@date_format,%Q\n";
+        let err = TimesheetFormat::from_reader(bad.as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "line 3: \"%Q\" isn't a date format");
+        assert!(TimesheetFormat::new().set_date_format("%d %H:%M").is_err());
+    }
+}
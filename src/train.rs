@@ -0,0 +1,181 @@
+//! The train module pairs trainees with staff able to train them whenever they work.
+use super::emp::{Employee, Roster};
+use super::time::{Day, Schedule, Shift, Time};
+use super::valid::Problem;
+#[cfg(feature = "project")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// ==============================================
+
+/// A trainee's need for someone with at least `min` ability at `kind` to be working alongside them.
+///
+/// If `events_only` is set, the need only applies to shifts working an event of that kind.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "project", derive(Serialize, Deserialize))]
+pub struct Training {
+    pub kind: String,
+    pub min: u8,
+    pub events_only: bool,
+}
+
+impl Training {
+    // Constructor
+    pub fn new(kind: &str, min: u8, events_only: bool) -> Training {
+        Training {
+            kind: kind.to_owned(),
+            min,
+            events_only,
+        }
+    }
+    // Access
+    pub fn is_trainer(&self, em: &Employee) -> bool {
+        //! Check if an employee is able enough to train under this rule.
+        em.get_abil(&self.kind).is_some_and(|a| a >= self.min)
+    }
+}
+
+impl fmt::Display for Training {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}+", self.kind, self.min)?;
+        if self.events_only {
+            write!(f, " at events")?;
+        }
+        Ok(())
+    }
+}
+
+// ==============================================
+
+/// The quarter-hours of one trainee's shift during which nobody is on hand to train them.
+#[derive(Clone, Debug)]
+pub(crate) struct Gap {
+    pub trainee: String,
+    pub start: usize,
+    pub end: usize,
+    pub rule: Training,
+    pub quarters: Vec<usize>,
+}
+
+impl Schedule {
+    fn event_kind(&self, day: &Day, sh: &Shift) -> Option<&str> {
+        let name = sh.event.as_ref()?;
+        self.events
+            .iter()
+            .find(|ev| ev.name == *name && ev.day == *day)
+            .map(|ev| ev.kind.as_str())
+    }
+    fn trainer_at(
+        &self,
+        day: &Day,
+        qi: usize,
+        trainee: &str,
+        rule: &Training,
+        ros: &Roster,
+    ) -> bool {
+        self.shifts(day).iter().any(|sh| {
+            sh.emp_id != trainee
+                && sh.start.get_qi() <= qi
+                && qi < sh.end.get_qi()
+                && ros.get(&sh.emp_id).is_some_and(|em| rule.is_trainer(em))
+        })
+    }
+    pub(crate) fn training_gaps(&self, day: &Day, ros: &Roster) -> Vec<Gap> {
+        let mut out = Vec::new();
+        for sh in self.shifts(day) {
+            let em = match ros.get(&sh.emp_id) {
+                Some(em) => em,
+                None => continue,
+            };
+            for rule in em.training() {
+                if rule.events_only && self.event_kind(day, sh) != Some(rule.kind.as_str()) {
+                    continue;
+                }
+                let quarters: Vec<usize> = (sh.start.get_qi()..sh.end.get_qi())
+                    .filter(|qi| !self.trainer_at(day, *qi, &sh.emp_id, rule, ros))
+                    .collect();
+                if !quarters.is_empty() {
+                    out.push(Gap {
+                        trainee: sh.emp_id.clone(),
+                        start: sh.start.get_qi(),
+                        end: sh.end.get_qi(),
+                        rule: rule.clone(),
+                        quarters,
+                    });
+                }
+            }
+        }
+        out
+    }
+    // Validation
+    pub fn training_problems(&self, em: &Employee, ros: &Roster) -> Vec<Problem> {
+        //! List every shift a trainee works without someone able to train them there the whole time.
+        let mut out = Vec::new();
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            for gap in self.training_gaps(&day, ros) {
+                if gap.trainee == em.id() {
+                    out.push(Problem::Untrained {
+                        emp_id: gap.trainee,
+                        day: day.clone(),
+                        start: Time::from_qi(gap.start),
+                        training: gap.rule.to_string(),
+                    });
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use crate::gen::Objective;
+    fn setup() -> Fixture {
+        Fixture::new()
+            .employee("Newt", |em| {
+                em.set_abil("Magic", 1);
+                em.add_training(Training::new("Magic", 3, false));
+            })
+            .employee("Vera", |em| em.set_abil("Magic", 4))
+            .shift("Newt", Day::Saturday, "12:00", "16:00")
+    }
+    #[test]
+    fn needs_trainer() {
+        let (mut sched, ros) = setup().build();
+        let newt = ros.get("Newt").unwrap();
+        assert_eq!(sched.training_problems(newt, &ros).len(), 1);
+        sched.assign_shift(
+            "Vera".to_string(),
+            Day::Saturday,
+            Time::from_hour(10),
+            Time::from_hour(15),
+        );
+        assert_eq!(
+            sched.training_gaps(&Day::Saturday, &ros)[0].quarters.len(),
+            4
+        );
+        sched.assign_shift(
+            "Vera".to_string(),
+            Day::Saturday,
+            Time::from_hour(15),
+            Time::from_hour(18),
+        );
+        assert!(sched.training_problems(newt, &ros).is_empty());
+    }
+    #[test]
+    fn generation_adds_trainer() {
+        let (mut sched, ros) = setup().need(Day::Saturday, "10:00", "18:00", 1).build();
+        sched.fill_shifts(&ros, &Objective::default());
+        assert!(!sched
+            .problems(&ros)
+            .iter()
+            .any(|p| matches!(p, Problem::Untrained { .. })));
+        assert!(sched
+            .shifts(&Day::Saturday)
+            .iter()
+            .any(|sh| sh.emp_id == "Vera" && sh.start.get_qi() <= 48 && sh.end.get_qi() >= 64));
+    }
+}
@@ -0,0 +1,818 @@
+//! The valid module checks schedules against the hard rules of the roster and store, and vets shift trades.
+use super::emp::Roster;
+use super::time::{Day, Schedule, Time};
+use chrono::NaiveDate;
+use std::fmt;
+
+// ==============================================
+
+/// A single way in which a schedule breaks the rules.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    /// A shift is assigned to someone who isn't on the roster.
+    UnknownEmployee { emp_id: String },
+    /// An employee's weekly total, in quarter-hours, is outside their hours range.
+    Hours {
+        emp_id: String,
+        assigned: usize,
+        min: usize,
+        max: usize,
+    },
+    /// A shift falls outside the employee's availability.
+    Unavailable {
+        emp_id: String,
+        day: Day,
+        start: Time,
+        end: Time,
+    },
+    /// A shift overlaps approved time off.
+    TimeOff {
+        emp_id: String,
+        date: NaiveDate,
+        start: Time,
+        end: Time,
+    },
+    /// An employee with approved time off is scheduled, but the schedule has no week to check it against.
+    Undated { emp_id: String },
+    /// An employee works an event without the ability to run it.
+    Unable {
+        emp_id: String,
+        event: String,
+        kind: String,
+    },
+    /// An employee gets too short a break, in quarter-hours, before their first shift on `day`.
+    Rest {
+        emp_id: String,
+        day: Day,
+        rest: usize,
+        min: usize,
+    },
+    /// An employee is assigned two overlapping shifts.
+    DoubleBooked {
+        emp_id: String,
+        day: Day,
+        start: Time,
+        end: Time,
+    },
+    /// Fewer staff are on hand than required.
+    Coverage {
+        day: Day,
+        time: Time,
+        have: i32,
+        need: i32,
+    },
+    /// A shift breaks a rule from a selected labor-law pack.
+    Law {
+        emp_id: String,
+        day: Day,
+        pack: String,
+        rule: String,
+    },
+    /// The schedule was published with fewer days' notice than a selected labor-law pack requires.
+    Notice {
+        pack: String,
+        given: i64,
+        required: i64,
+    },
+    /// The week's labor costs more than the budget allows.
+    OverBudget { cost: f64, budget: f64 },
+    /// An employee doesn't have time to travel between shifts at two stores.
+    Travel {
+        emp_id: String,
+        day: Day,
+        from: String,
+        to: String,
+        gap: usize,
+        needed: usize,
+    },
+    /// A trainee works the shift starting at `start` without someone able to train them there throughout.
+    Untrained {
+        emp_id: String,
+        day: Day,
+        start: Time,
+        training: String,
+    },
+    /// An employee breaks a rule about working together with, or apart from, someone else on `day`.
+    Pairing {
+        emp_id: String,
+        day: Day,
+        rule: String,
+    },
+    /// An employee is posted to a position they aren't eligible for.
+    Ineligible {
+        emp_id: String,
+        day: Day,
+        position: String,
+    },
+    /// Fewer staff are posted to a position than it requires.
+    PositionCoverage {
+        position: String,
+        day: Day,
+        time: Time,
+        have: i32,
+        need: i32,
+    },
+    /// Nobody with the store keys is working the start of the pre-open window, or the end of the post-close one.
+    NoKeys { day: Day, opening: bool },
+    /// A shift named in a trade doesn't exist.
+    MissingShift(ShiftKey),
+}
+
+impl Problem {
+    pub fn emp_id(&self) -> Option<&str> {
+        //! Return the ID of the employee this problem concerns, if any.
+        match self {
+            Problem::UnknownEmployee { emp_id }
+            | Problem::Hours { emp_id, .. }
+            | Problem::Unavailable { emp_id, .. }
+            | Problem::TimeOff { emp_id, .. }
+            | Problem::Undated { emp_id }
+            | Problem::Unable { emp_id, .. }
+            | Problem::Rest { emp_id, .. }
+            | Problem::DoubleBooked { emp_id, .. }
+            | Problem::Law { emp_id, .. }
+            | Problem::Travel { emp_id, .. }
+            | Problem::Untrained { emp_id, .. }
+            | Problem::Pairing { emp_id, .. }
+            | Problem::Ineligible { emp_id, .. } => Some(emp_id),
+            Problem::MissingShift(key) => Some(&key.emp_id),
+            Problem::Coverage { .. }
+            | Problem::PositionCoverage { .. }
+            | Problem::NoKeys { .. }
+            | Problem::Notice { .. }
+            | Problem::OverBudget { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::UnknownEmployee { emp_id } => write!(f, "{} is not on the roster", emp_id),
+            Problem::Hours {
+                emp_id,
+                assigned,
+                min,
+                max,
+            } => write!(
+                f,
+                "{} has {} hours, outside {} - {}",
+                emp_id,
+                Time::duration_string(*assigned),
+                min / 4,
+                max / 4
+            ),
+            Problem::Unavailable {
+                emp_id,
+                day,
+                start,
+                end,
+            } => write!(
+                f,
+                "{} is not available {} - {} on {}",
+                emp_id, start, end, day
+            ),
+            Problem::TimeOff {
+                emp_id,
+                date,
+                start,
+                end,
+            } => write!(
+                f,
+                "{} has approved time off during {} - {} on {}",
+                emp_id, start, end, date
+            ),
+            Problem::Undated { emp_id } => write!(
+                f,
+                "{} has approved time off, but the schedule has no week to check it against",
+                emp_id
+            ),
+            Problem::Unable {
+                emp_id,
+                event,
+                kind,
+            } => write!(f, "{} can't run {} ({})", emp_id, event, kind),
+            Problem::Rest {
+                emp_id,
+                day,
+                rest,
+                min,
+            } => write!(
+                f,
+                "{} only rests {} before {}, needs {}",
+                emp_id,
+                Time::duration_string(*rest),
+                day,
+                Time::duration_string(*min)
+            ),
+            Problem::DoubleBooked {
+                emp_id,
+                day,
+                start,
+                end,
+            } => write!(
+                f,
+                "{} is double-booked {} - {} on {}",
+                emp_id, start, end, day
+            ),
+            Problem::Coverage {
+                day,
+                time,
+                have,
+                need,
+            } => write!(
+                f,
+                "Low coverage at {} on {}: {} of {}",
+                time, day, have, need
+            ),
+            Problem::Law {
+                emp_id,
+                day,
+                pack,
+                rule,
+            } => write!(f, "{} breaks {} on {}: {}", emp_id, pack, day, rule),
+            Problem::Notice {
+                pack,
+                given,
+                required,
+            } => write!(
+                f,
+                "{} requires {} days' notice, schedule gives {}",
+                pack, required, given
+            ),
+            Problem::OverBudget { cost, budget } => {
+                write!(f, "Labor costs {:.2}, over the {:.2} budget", cost, budget)
+            }
+            Problem::Travel {
+                emp_id,
+                day,
+                from,
+                to,
+                gap,
+                needed,
+            } => write!(
+                f,
+                "{} has {} to get from {} to {} on {}, needs {}",
+                emp_id,
+                Time::duration_string(*gap),
+                from,
+                to,
+                day,
+                Time::duration_string(*needed)
+            ),
+            Problem::Untrained {
+                emp_id,
+                day,
+                start,
+                training,
+            } => write!(
+                f,
+                "{} needs a trainer ({}) during their {} shift on {}",
+                emp_id, training, start, day
+            ),
+            Problem::Pairing { emp_id, day, rule } => {
+                write!(f, "{} breaks \"{}\" on {}", emp_id, rule, day)
+            }
+            Problem::Ineligible {
+                emp_id,
+                day,
+                position,
+            } => write!(f, "{} can't work {} on {}", emp_id, position, day),
+            Problem::PositionCoverage {
+                position,
+                day,
+                time,
+                have,
+                need,
+            } => write!(
+                f,
+                "Low {} coverage at {} on {}: {} of {}",
+                position, time, day, have, need
+            ),
+            Problem::NoKeys { day, opening: true } => {
+                write!(f, "Nobody with keys opens on {}", day)
+            }
+            Problem::NoKeys {
+                day,
+                opening: false,
+            } => write!(f, "Nobody with keys closes on {}", day),
+            Problem::MissingShift(key) => write!(f, "No such shift: {}", key),
+        }
+    }
+}
+
+pub(crate) fn worsens(new: &Problem, old: &Problem) -> Option<bool> {
+    //! If `old` is the same problem as `new` for the same subject, return whether `new` is further from meeting
+    //! the rule; otherwise return None.
+    let outside = |assigned: usize, min: usize, max: usize| {
+        min.saturating_sub(assigned) + assigned.saturating_sub(max)
+    };
+    match (new, old) {
+        (
+            Problem::Hours {
+                emp_id,
+                assigned,
+                min,
+                max,
+            },
+            Problem::Hours {
+                emp_id: was_id,
+                assigned: was,
+                min: was_min,
+                max: was_max,
+            },
+        ) if emp_id == was_id => {
+            Some(outside(*assigned, *min, *max) > outside(*was, *was_min, *was_max))
+        }
+        (
+            Problem::Rest {
+                emp_id, day, rest, ..
+            },
+            Problem::Rest {
+                emp_id: was_id,
+                day: was_day,
+                rest: was,
+                ..
+            },
+        ) if emp_id == was_id && day == was_day => Some(rest < was),
+        (
+            Problem::Coverage {
+                day,
+                time,
+                have,
+                need,
+            },
+            Problem::Coverage {
+                day: was_day,
+                time: was_time,
+                have: was,
+                need: was_need,
+            },
+        ) if day == was_day && time == was_time => Some(need - have > was_need - was),
+        (
+            Problem::PositionCoverage {
+                position,
+                day,
+                time,
+                have,
+                need,
+            },
+            Problem::PositionCoverage {
+                position: was_position,
+                day: was_day,
+                time: was_time,
+                have: was,
+                need: was_need,
+            },
+        ) if position == was_position && day == was_day && time == was_time => {
+            Some(need - have > was_need - was)
+        }
+        (
+            Problem::Travel {
+                emp_id,
+                day,
+                from,
+                to,
+                gap,
+                needed,
+            },
+            Problem::Travel {
+                emp_id: was_id,
+                day: was_day,
+                from: was_from,
+                to: was_to,
+                gap: was,
+                needed: was_needed,
+            },
+        ) if emp_id == was_id && day == was_day && from == was_from && to == was_to => {
+            Some(needed.saturating_sub(*gap) > was_needed.saturating_sub(*was))
+        }
+        (
+            Problem::OverBudget { cost, budget },
+            Problem::OverBudget {
+                cost: was,
+                budget: was_budget,
+            },
+        ) => Some(cost - budget > was - was_budget),
+        _ if new == old => Some(false),
+        _ => None,
+    }
+}
+
+// ==============================================
+
+/// Identifies one shift on a schedule by who works it, on which day, and when it starts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShiftKey {
+    pub emp_id: String,
+    pub day: Day,
+    pub start: Time,
+}
+
+impl ShiftKey {
+    pub fn new(emp_id: &str, day: Day, start: Time) -> ShiftKey {
+        ShiftKey {
+            emp_id: emp_id.to_string(),
+            day,
+            start,
+        }
+    }
+}
+
+impl fmt::Display for ShiftKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} on {} at {}", self.emp_id, self.day, self.start)
+    }
+}
+
+/// A change of hands between employees.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Trade {
+    /// Two employees exchange shifts.
+    Swap(ShiftKey, ShiftKey),
+    /// An employee gives a shift away to the employee with the given ID.
+    Giveaway(ShiftKey, String),
+}
+
+// ==============================================
+
+impl Schedule {
+    // Validation
+    pub fn employee_problems(&self, id: &str, ros: &Roster) -> Vec<Problem> {
+        //! List every rule this schedule breaks for a single employee.
+        let mut out = Vec::new();
+        let em = match ros.get(id) {
+            Some(em) => em,
+            None => {
+                if (0..7).any(|i| self.shifts[i].iter().any(|sh| sh.emp_id == id)) {
+                    out.push(Problem::UnknownEmployee {
+                        emp_id: id.to_string(),
+                    });
+                }
+                return out;
+            }
+        };
+        let mut total = 0;
+        let mut last_end: Option<usize> = None;
+        for (i, day_shifts) in self.shifts.iter().enumerate() {
+            let day = Day::from_index(i).unwrap();
+            let date = self.date_of(&day);
+            let mine: Vec<_> = day_shifts.iter().filter(|sh| sh.emp_id == id).collect();
+            for (j, sh) in mine.iter().enumerate() {
+                total += sh.len();
+                if !em.is_available_for(&day, &sh.start, &sh.end) {
+                    out.push(Problem::Unavailable {
+                        emp_id: id.to_string(),
+                        day: day.clone(),
+                        start: sh.start.clone(),
+                        end: sh.end.clone(),
+                    });
+                }
+                if let Some(date) = date {
+                    if em.is_on_leave(date, &sh.start, &sh.end) {
+                        out.push(Problem::TimeOff {
+                            emp_id: id.to_string(),
+                            date,
+                            start: sh.start.clone(),
+                            end: sh.end.clone(),
+                        });
+                    }
+                }
+                if let Some(name) = &sh.event {
+                    let kind = self
+                        .events
+                        .iter()
+                        .find(|ev| ev.name == *name && ev.day == day)
+                        .map(|ev| ev.kind.clone());
+                    if let Some(kind) = kind {
+                        if !em.is_able(&kind) {
+                            out.push(Problem::Unable {
+                                emp_id: id.to_string(),
+                                event: name.clone(),
+                                kind,
+                            });
+                        }
+                    }
+                }
+                for other in mine[j + 1..].iter() {
+                    if other.start.get_qi() < sh.end.get_qi()
+                        && sh.start.get_qi() < other.end.get_qi()
+                    {
+                        out.push(Problem::DoubleBooked {
+                            emp_id: id.to_string(),
+                            day: day.clone(),
+                            start: other.start.clone(),
+                            end: other.end.clone(),
+                        });
+                    }
+                }
+            }
+            let first_start = mine.iter().map(|sh| sh.start.get_qi()).min();
+            if let (Some(end), Some(start)) = (last_end, first_start) {
+                let rest = 24 * 4 - end + start;
+                if rest < self.min_rest() {
+                    out.push(Problem::Rest {
+                        emp_id: id.to_string(),
+                        day: day.clone(),
+                        rest,
+                        min: self.min_rest(),
+                    });
+                }
+            }
+            last_end = mine.iter().map(|sh| sh.end.get_qi()).max();
+        }
+        if total > 0 && self.week().is_none() && em.time_off().iter().any(|req| req.is_approved()) {
+            out.push(Problem::Undated {
+                emp_id: id.to_string(),
+            });
+        }
+        out.extend(self.law_problems(em));
+        out.extend(self.eligibility_problems(em));
+        out.extend(self.training_problems(em, ros));
+        out.extend(self.pairing_problems(em, ros));
+        let min = em.get_hours().min() * 4;
+        let max = em.get_hours().max() * 4;
+        if !(min..=max).contains(&total) {
+            out.push(Problem::Hours {
+                emp_id: id.to_string(),
+                assigned: total,
+                min,
+                max,
+            });
+        }
+        out
+    }
+    pub fn coverage_problems(&self) -> Vec<Problem> {
+        //! List every quarter-hour in which fewer staff are on hand than required.
+        let mut out = Vec::new();
+        for i in 0..7 {
+            let day = Day::from_index(i).unwrap();
+            let coverage = self.coverage(day.clone());
+            for (j, cover) in coverage.iter().enumerate() {
+                let need = self.get_req(&day, j);
+                if *cover < need {
+                    out.push(Problem::Coverage {
+                        day: day.clone(),
+                        time: Time::from_qi(j),
+                        have: *cover,
+                        need,
+                    });
+                }
+            }
+        }
+        out
+    }
+    pub(crate) fn emp_ids(&self, ros: &Roster) -> Vec<String> {
+        let mut ids: Vec<String> = ros.iter().map(|(id, _)| id.clone()).collect();
+        for day in self.shifts.iter() {
+            for sh in day.iter() {
+                if !ids.contains(&sh.emp_id) {
+                    ids.push(sh.emp_id.clone());
+                }
+            }
+        }
+        ids.sort();
+        ids
+    }
+    pub fn store_problems(&self, ros: &Roster) -> Vec<Problem> {
+        //! List every rule this schedule breaks as a store, rather than for any one employee's week. Notice rules
+        //! are checked once the schedule is published.
+        let mut out = self.coverage_problems();
+        out.extend(self.position_problems());
+        out.extend(self.key_problems(ros));
+        if let Some(published) = self.published() {
+            out.extend(self.notice_problems(published));
+        }
+        if let Some(budget) = self.wages().budget() {
+            let cost = self.cost(ros).total;
+            if cost > budget {
+                out.push(Problem::OverBudget { cost, budget });
+            }
+        }
+        out
+    }
+    pub fn problems(&self, ros: &Roster) -> Vec<Problem> {
+        //! List every rule this schedule breaks.
+        let mut out = Vec::new();
+        for id in self.emp_ids(ros).iter() {
+            out.extend(self.employee_problems(id, ros));
+        }
+        out.extend(self.store_problems(ros));
+        out
+    }
+    pub fn is_valid(&self, ros: &Roster) -> bool {
+        //! Check this schedule against every rule, printing any it breaks.
+        let problems = self.problems(ros);
+        for p in problems.iter() {
+            println!("{}", p);
+        }
+        problems.is_empty()
+    }
+    // Trades
+    fn find_shift(&self, key: &ShiftKey) -> Option<usize> {
+        self.shifts[key.day.to_index()]
+            .iter()
+            .position(|sh| sh.emp_id == key.emp_id && sh.start == key.start)
+    }
+    fn apply_trade(&mut self, trade: &Trade) -> Result<(), Problem> {
+        match trade {
+            Trade::Swap(a, b) => {
+                let a_i = self
+                    .find_shift(a)
+                    .ok_or_else(|| Problem::MissingShift(a.clone()))?;
+                let b_i = self
+                    .find_shift(b)
+                    .ok_or_else(|| Problem::MissingShift(b.clone()))?;
+                self.shifts[a.day.to_index()][a_i].emp_id = b.emp_id.clone();
+                self.shifts[b.day.to_index()][b_i].emp_id = a.emp_id.clone();
+            }
+            Trade::Giveaway(a, to) => {
+                let a_i = self
+                    .find_shift(a)
+                    .ok_or_else(|| Problem::MissingShift(a.clone()))?;
+                self.shifts[a.day.to_index()][a_i].emp_id = to.clone();
+            }
+        }
+        Ok(())
+    }
+    pub fn check_trade(&self, trade: &Trade, ros: &Roster) -> Result<(), Vec<Problem>> {
+        //! Check whether a trade may go ahead without changing this schedule.
+        //!
+        //! A trade is refused if it leaves the schedule breaking a rule it wasn't already breaking, for anyone or
+        //! for the store, or further from meeting a rule it already broke, such as someone's hours range or the
+        //! labor budget; the error lists those problems.
+        let mut after = self.clone();
+        after.apply_trade(trade).map_err(|p| vec![p])?;
+        let before = self.problems(ros);
+        let mut out = Vec::new();
+        for p in after.problems(ros) {
+            let known = before.iter().any(|was| worsens(&p, was) == Some(false));
+            if !known && !out.contains(&p) {
+                out.push(p);
+            }
+        }
+        if out.is_empty() {
+            Ok(())
+        } else {
+            Err(out)
+        }
+    }
+    pub fn trade(&mut self, trade: &Trade, ros: &Roster) -> Result<(), Vec<Problem>> {
+        //! Apply a trade if `check_trade` allows it, leaving this schedule untouched otherwise.
+        self.check_trade(trade, ros)?;
+        self.apply_trade(trade).map_err(|p| vec![p])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emp::TimeOff;
+    use crate::fixture::Fixture;
+    use crate::time::Window;
+    fn setup() -> Fixture {
+        Fixture::new()
+            .staff(&["Ann"])
+            .employee("Bob", |em| {
+                em.set_windows(Day::Tuesday, vec![Window::from_str("15:00-23:45").unwrap()])
+            })
+            .shift("Ann", Day::Tuesday, "9:00", "15:00")
+            .shift("Bob", Day::Wednesday, "9:00", "15:00")
+            .shift("Ann", Day::Thursday, "9:00", "15:00")
+    }
+    #[test]
+    fn swap_ok() {
+        let (mut sched, ros) = setup().build();
+        let t = Trade::Swap(
+            ShiftKey::new("Ann", Day::Thursday, Time::from_hour(9)),
+            ShiftKey::new("Bob", Day::Wednesday, Time::from_hour(9)),
+        );
+        assert_eq!(sched.trade(&t, &ros), Ok(()));
+        assert_eq!(sched.shifts(&Day::Thursday)[0].emp_id, "Bob");
+    }
+    #[test]
+    fn giveaway_unavailable() {
+        let (mut sched, ros) = setup().build();
+        let t = Trade::Giveaway(
+            ShiftKey::new("Ann", Day::Tuesday, Time::from_hour(9)),
+            "Bob".to_string(),
+        );
+        let err = sched.trade(&t, &ros).unwrap_err();
+        assert!(matches!(err[0], Problem::Unavailable { .. }));
+        assert_eq!(sched.shifts(&Day::Tuesday)[0].emp_id, "Ann");
+    }
+    #[test]
+    fn giveaway_toward_hours() {
+        let (mut sched, mut ros) = setup().build();
+        ros.get_mut("Bob").unwrap().hours().set(38, 40);
+        // Bob is short of his minimum either way, but taking a shift brings him closer.
+        let t = Trade::Giveaway(
+            ShiftKey::new("Ann", Day::Thursday, Time::from_hour(9)),
+            "Bob".to_string(),
+        );
+        assert_eq!(sched.check_trade(&t, &ros), Ok(()));
+        let t = Trade::Giveaway(
+            ShiftKey::new("Bob", Day::Wednesday, Time::from_hour(9)),
+            "Ann".to_string(),
+        );
+        let err = sched.trade(&t, &ros).unwrap_err();
+        assert!(matches!(&err[..], [Problem::Hours { assigned: 0, .. }]));
+    }
+    #[test]
+    fn giveaway_keeps_keys() {
+        let (mut sched, ros) = setup()
+            .employee("Kim", |em| em.add_role("Keyholder"))
+            .shift("Kim", Day::Saturday, "9:45", "17:00")
+            .build();
+        sched.set_key_role(Some("Keyholder"));
+        sched.set_hours(Day::Saturday, 10, 16);
+        // Ann has no keys, so the store would go unopened and unclosed.
+        let t = Trade::Giveaway(
+            ShiftKey::new("Kim", Day::Saturday, Time::from_str("9:45")),
+            "Ann".to_string(),
+        );
+        let err = sched.trade(&t, &ros).unwrap_err();
+        assert_eq!(
+            err,
+            vec![
+                Problem::NoKeys {
+                    day: Day::Saturday,
+                    opening: true
+                },
+                Problem::NoKeys {
+                    day: Day::Saturday,
+                    opening: false
+                },
+            ]
+        );
+        assert_eq!(sched.shifts(&Day::Saturday)[0].emp_id, "Kim");
+    }
+    #[test]
+    fn cheaper_over_budget() {
+        let mut fix = Fixture::new()
+            .employee("Bob", |em| em.set_rate(Some(20.0)))
+            .employee("Kim", |em| em.set_rate(Some(10.0)));
+        for i in 0..6 {
+            fix = fix.shift("Bob", Day::from_index(i).unwrap(), "9:00", "17:00");
+        }
+        let (mut sched, ros) = fix.build();
+        sched.wages_mut().set_budget(Some(500.0));
+        assert_eq!(sched.cost(&ros).total, 1040.0);
+        // Still over budget afterwards, but by less.
+        let t = Trade::Giveaway(
+            ShiftKey::new("Bob", Day::Thursday, Time::from_hour(9)),
+            "Kim".to_string(),
+        );
+        assert_eq!(sched.trade(&t, &ros), Ok(()));
+        assert_eq!(sched.cost(&ros).total, 880.0);
+        let t = Trade::Giveaway(
+            ShiftKey::new("Kim", Day::Thursday, Time::from_hour(9)),
+            "Bob".to_string(),
+        );
+        assert!(matches!(
+            &sched.check_trade(&t, &ros).unwrap_err()[..],
+            [Problem::Hours { .. }, Problem::OverBudget { .. }]
+        ));
+    }
+    #[test]
+    fn time_off_needs_dates() {
+        let tuesday = crate::fixture::week() + chrono::Duration::days(3);
+        let (mut sched, mut ros) = setup().build();
+        let ann = ros.get_mut("Ann").unwrap();
+        let i = ann.request_time_off(TimeOff::new(tuesday, tuesday));
+        ann.time_off_mut(i).unwrap().approve();
+        assert_eq!(
+            sched.employee_problems("Ann", &ros),
+            vec![Problem::Undated {
+                emp_id: "Ann".to_string()
+            }]
+        );
+        sched.set_week(tuesday);
+        assert!(matches!(
+            &sched.employee_problems("Ann", &ros)[..],
+            [Problem::TimeOff { date, .. }] if *date == tuesday
+        ));
+    }
+    #[test]
+    fn rest_and_missing() {
+        let (mut sched, ros) = setup().build();
+        sched.assign_shift(
+            "Bob".to_string(),
+            Day::Tuesday,
+            Time::from_hour(17),
+            Time::from_str("23:30"),
+        );
+        assert!(sched
+            .employee_problems("Bob", &ros)
+            .iter()
+            .any(|p| matches!(p, Problem::Rest { .. })));
+        let t = Trade::Giveaway(
+            ShiftKey::new("Ann", Day::Monday, Time::from_hour(9)),
+            "Bob".to_string(),
+        );
+        assert!(matches!(
+            sched.check_trade(&t, &ros).unwrap_err()[0],
+            Problem::MissingShift(_)
+        ));
+    }
+}